@@ -1,13 +1,23 @@
-use super::verify_path;
+use super::{verify_file, verify_path};
+use axum::http::Method;
 use clap::Parser;
-use std::path::PathBuf;
+use std::{fmt, net::IpAddr, path::PathBuf, str::FromStr, time::Duration};
 
 // HTTP子命令枚举，用于处理HTTP相关的命令行操作
 #[derive(Debug, Parser)]
 pub enum HttpSubCommand {
     // Serve子命令，用于启动HTTP文件服务器
     #[command(about = "Serve a directory over HTTP")]
-    Serve(HttpServeOpts),
+    Serve(Box<HttpServeOpts>),
+    // SignUrl子命令，用于生成带过期时间的签名下载链接
+    #[command(name = "sign-url", about = "Sign a time-limited download link")]
+    SignUrl(HttpSignUrlOpts),
+    // Fetch子命令，按照签名的文件清单下载并校验整个目录
+    #[command(about = "Download a served directory and verify it against its manifest")]
+    Fetch(HttpFetchOpts),
+    // Request子命令，发送单个HTTP请求或执行.http文件
+    #[command(about = "Send an HTTP request or run the requests in a .http file")]
+    Request(HttpRequestOpts),
 }
 
 // HTTP服务器选项结构体，定义服务器的配置参数
@@ -19,4 +29,519 @@ pub struct HttpServeOpts {
     // 服务端口号，使用-p或--port指定，默认为8080
     #[arg(short, long, default_value_t = 8080)]
     pub port: u16,
+    // 绑定的IP地址，支持IPv4与IPv6，默认为0.0.0.0（所有网卡），127.0.0.1只允许本机访问
+    #[arg(short, long, default_value = "0.0.0.0")]
+    pub bind: IpAddr,
+    // HTTPS证书文件（PEM格式），需要与--tls-key一起使用
+    #[arg(long, value_parser = verify_file, requires = "tls_key")]
+    pub tls_cert: Option<String>,
+    // HTTPS私钥文件（PEM格式），需要与--tls-cert一起使用
+    #[arg(long, value_parser = verify_file, requires = "tls_cert")]
+    pub tls_key: Option<String>,
+    // 自动生成临时自签名证书启用HTTPS
+    #[arg(long, conflicts_with = "tls_cert", default_value_t = false)]
+    pub tls_self_signed: bool,
+    // 基本认证凭据，格式为user:pass，可重复指定；密码也可以是bcrypt哈希
+    #[arg(long, value_name = "USER:PASS")]
+    pub auth: Vec<String>,
+    // 基本认证凭据文件，每行一个user:pass，支持htpasswd -B生成的bcrypt哈希
+    #[arg(long, value_parser = verify_file)]
+    pub auth_file: Option<String>,
+    // Bearer认证令牌，可重复指定
+    #[arg(long)]
+    pub token: Vec<String>,
+    // 是否允许跟随指向服务目录之外的符号链接，默认拒绝
+    #[arg(long, default_value_t = false)]
+    pub follow_symlinks: bool,
+    // 文件响应附带的Cache-Control头，例如"no-cache"或"public, max-age=3600"
+    #[arg(long)]
+    pub cache_control: Option<String>,
+    // 签名链接使用的blake3密钥文件，设置后文件、模拟路由与回显只允许带有效签名的请求
+    #[arg(long, value_parser = verify_file)]
+    pub signed_urls_key: Option<String>,
+    // 是否允许通过PUT或multipart表单上传文件
+    #[arg(long, default_value_t = false)]
+    pub upload: bool,
+    // 单个上传文件的最大大小，支持K/M/G后缀，默认为100M
+    #[arg(long, value_parser = parse_size, default_value = "100M")]
+    pub max_upload_size: u64,
+    // 上传与WebDAV写入时禁止覆盖已有文件
+    #[arg(long, default_value_t = false)]
+    pub no_clobber: bool,
+    // 以WebDAV方式提供读写访问（PROPFIND、MKCOL、MOVE、COPY、DELETE、PUT与内存中的LOCK）
+    #[arg(long, default_value_t = false)]
+    pub webdav: bool,
+    // 在文件响应中附带blake3的Repr-Digest/Digest头，并在/_manifest.json提供所有文件的哈希清单
+    #[arg(long, default_value_t = false)]
+    pub integrity: bool,
+    // 用于签名文件清单的Ed25519私钥（rcli text generate-key生成的ed25519.sk），
+    // 设置后提供/_manifest.json与分离签名/_manifest.json.sig
+    #[arg(long, value_parser = verify_file)]
+    pub sign_manifest: Option<String>,
+    // 将.md文件渲染为带目录与代码高亮的HTML页面，?raw=1返回原始内容
+    #[arg(long, default_value_t = false)]
+    pub render_markdown: bool,
+    // 关闭浏览器访问CSV/JSON/YAML文件时的表格与树形预览页面
+    #[arg(long, default_value_t = false)]
+    pub no_preview: bool,
+    // 启用的实时压缩算法，逗号分隔，默认全部启用
+    #[arg(long, value_delimiter = ',', value_parser = parse_compression, default_value = "gzip,br,deflate,zstd")]
+    pub compress: Vec<CompressionAlgorithm>,
+    // 关闭响应的实时压缩
+    #[arg(long, default_value_t = false)]
+    pub no_compress: bool,
+    // 启用实时压缩的最小响应大小（字节）
+    #[arg(long, default_value_t = 1024)]
+    pub compress_min_size: u16,
+    // 不使用预压缩的同名文件（.br/.zst/.gz）
+    #[arg(long, default_value_t = false)]
+    pub no_precompressed: bool,
+    // 启用CORS跨域支持
+    #[arg(long, default_value_t = false)]
+    pub cors: bool,
+    // CORS允许的来源，可重复指定，默认允许任意来源
+    #[arg(long, requires = "cors")]
+    pub cors_origin: Vec<String>,
+    // CORS允许的请求方法，逗号分隔
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_value = "GET,HEAD,OPTIONS",
+        requires = "cors"
+    )]
+    pub cors_methods: Vec<String>,
+    // 访问日志输出位置，"-"表示标准输出，其他值表示追加写入的文件
+    #[arg(long)]
+    pub access_log: Option<String>,
+    // 访问日志格式：common、combined或json
+    #[arg(long, value_parser = parse_access_log_format, default_value = "combined")]
+    pub access_log_format: AccessLogFormat,
+    // 请求目录时返回的索引文件名，如index.html
+    #[arg(long)]
+    pub index: Option<String>,
+    // 单页应用模式，找不到的路径回退到根目录的索引文件
+    #[arg(long, default_value_t = false)]
+    pub spa: bool,
+    // 自定义404页面
+    #[arg(long = "404-page", value_parser = verify_file)]
+    pub not_found_page: Option<String>,
+    // 支持省略.html扩展名的链接，如/about对应about.html
+    #[arg(long, default_value_t = false)]
+    pub clean_urls: bool,
+    // 监听目录变更，并让浏览器中打开的HTML页面自动刷新
+    #[arg(long, default_value_t = false)]
+    pub watch: bool,
+    // 反向代理路由，格式为PREFIX=URL，如/api=http://127.0.0.1:3000，可重复指定
+    #[arg(long, value_parser = parse_proxy_route)]
+    pub proxy: Vec<ProxyRoute>,
+    // 额外挂载的目录，格式为PREFIX=DIR，如/docs=./target/doc，可重复指定
+    #[arg(long, value_parser = parse_mount_point)]
+    pub mount: Vec<MountPoint>,
+    // 描述挂载点及其认证、响应头、缓存与目录列表设置的配置文件，如rcli-serve.toml
+    #[arg(long, value_parser = verify_file)]
+    pub config: Option<String>,
+    // 附加到响应的头部，格式为"Name: value"，可重复指定
+    #[arg(long)]
+    pub header: Vec<String>,
+    // 不返回目录列表
+    #[arg(long, default_value_t = false)]
+    pub no_listing: bool,
+    // 收到SIGINT/SIGTERM后等待进行中请求完成的最长时间
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "30s")]
+    pub shutdown_timeout: Duration,
+    // 最大并发连接数，超出的新连接直接关闭
+    #[arg(long)]
+    pub max_connections: Option<usize>,
+    // 单个请求生成响应头的超时时间，超时返回408；上传与WebDAV写入不受限制
+    #[arg(long, value_parser = humantime::parse_duration)]
+    pub request_timeout: Option<Duration>,
+    // 请求体的最大大小，支持K/M/G后缀，超出返回413
+    #[arg(long, value_parser = parse_size)]
+    pub max_body_size: Option<u64>,
+    // 每个客户端IP的请求频率限制，格式为N/s、N/m或N/h，超出返回429
+    #[arg(long, value_parser = parse_rate_limit)]
+    pub rate_limit: Option<RateLimit>,
+    // 模拟路由文件（YAML），匹配的请求直接返回文件中定义的响应
+    #[arg(long, value_parser = verify_file)]
+    pub mock: Option<String>,
+    // 提供/_echo端点，以JSON回显请求（包括Authorization等请求头），指定--mock时自动开启
+    #[arg(long)]
+    pub echo: bool,
+    // 启用的管理端点，逗号分隔：health（/_health）、metrics（/_metrics）、info（/_info）
+    #[arg(long, value_delimiter = ',', value_parser = parse_admin_endpoint)]
+    pub admin: Vec<AdminEndpoint>,
+    // 在单独的端口上提供管理端点，不设置时与文件服务使用同一端口
+    #[arg(long, requires = "admin")]
+    pub admin_port: Option<u16>,
+    // 目录打包下载（?download=zip或?download=tar.gz）允许的最大总大小，支持K/M/G后缀
+    #[arg(long, value_parser = parse_size, default_value = "1G")]
+    pub max_archive_size: u64,
+}
+
+// 请求频率限制，每个周期内最多允许requests个请求
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub per: Duration,
+}
+
+// 挂载点，将目录挂载到指定的路径前缀下
+#[derive(Debug, Clone)]
+pub struct MountPoint {
+    // 路径前缀，以/开头且不以/结尾
+    pub prefix: String,
+    // 挂载的目录
+    pub dir: PathBuf,
+}
+
+// 反向代理路由，将以prefix开头的请求转发到target
+#[derive(Debug, Clone)]
+pub struct ProxyRoute {
+    // 路径前缀，以/开头且不以/结尾
+    pub prefix: String,
+    // 后端地址，只支持http
+    pub target: axum::http::Uri,
+}
+
+// 访问日志格式枚举
+#[derive(Debug, Clone, Copy)]
+pub enum AccessLogFormat {
+    Common,   // Common Log Format
+    Combined, // Combined Log Format，额外包含Referer和User-Agent
+    Json,     // 每行一个JSON对象
+}
+
+// 支持的压缩算法枚举
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionAlgorithm {
+    Gzip,
+    Br,
+    Deflate,
+    Zstd,
+}
+
+// 管理端点枚举
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdminEndpoint {
+    Health,  // 存活检查
+    Metrics, // Prometheus格式的指标
+    Info,    // 版本、根目录与运行时间
+}
+
+// 签名链接选项结构体
+#[derive(Debug, Parser)]
+pub struct HttpSignUrlOpts {
+    // 需要签名的路径，例如/docs/report.pdf
+    pub path: String,
+    // blake3密钥文件，需要与http serve --signed-urls-key使用同一个
+    #[arg(short, long, value_parser = verify_file)]
+    pub key: String,
+    // 链接有效期，例如30m、1h、7d
+    #[arg(short, long, value_parser = humantime::parse_duration, default_value = "1h")]
+    pub expires: Duration,
+    // 链接的服务地址前缀
+    #[arg(long, default_value = "http://localhost:8080")]
+    pub base_url: String,
+}
+
+// 下载选项
+#[derive(Debug, Parser)]
+pub struct HttpFetchOpts {
+    // 目录的URL，支持http与https，例如http://localhost:8080/docs/
+    pub url: String,
+    // 保存的目录，不存在时自动创建
+    #[arg(short, long, default_value = ".")]
+    pub output: PathBuf,
+    // 校验清单签名的Ed25519公钥文件，需要与http serve --sign-manifest的私钥配对
+    #[arg(long, value_parser = verify_file)]
+    pub verify: Option<String>,
+}
+
+// HTTP请求选项
+#[derive(Debug, Parser)]
+pub struct HttpRequestOpts {
+    // 请求的URL，使用--file执行.http文件时不需要
+    #[arg(required_unless_present = "file")]
+    pub url: Option<String>,
+    // 请求方法，如GET、POST、PUT、DELETE
+    #[arg(short = 'X', long, value_parser = parse_method, default_value = "GET")]
+    pub method: Method,
+    // 请求头，格式为"Name: value"，可重复指定
+    #[arg(short = 'H', long)]
+    pub header: Vec<String>,
+    // JSON请求体，以@开头时读取文件内容，默认附带Content-Type: application/json
+    #[arg(long, conflicts_with_all = ["form", "data"])]
+    pub json: Option<String>,
+    // 表单字段，格式为KEY=VALUE，VALUE以@开头时作为文件上传并使用multipart编码，可重复指定
+    #[arg(short = 'F', long, conflicts_with = "data")]
+    pub form: Vec<String>,
+    // 原始请求体，以@开头时读取文件内容
+    #[arg(short, long)]
+    pub data: Option<String>,
+    // 要执行的.http文件，其中的请求按顺序发送并检查??断言
+    #[arg(short, long, value_parser = verify_file, conflicts_with_all = ["url", "json", "form", "data"])]
+    pub file: Option<String>,
+    // .http文件中的变量，格式为NAME=VALUE，覆盖文件中@NAME = VALUE的定义，可重复指定
+    #[arg(long, requires = "file")]
+    pub var: Vec<String>,
+    // 执行.http文件时同时输出每个响应的头部与内容
+    #[arg(short, long, default_value_t = false)]
+    pub verbose: bool,
+}
+
+// 自定义解析器，将字符串解析为CompressionAlgorithm枚举
+fn parse_compression(algorithm: &str) -> Result<CompressionAlgorithm, anyhow::Error> {
+    algorithm.parse()
+}
+
+// 实现从字符串到CompressionAlgorithm枚举的转换
+impl FromStr for CompressionAlgorithm {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gzip" => Ok(CompressionAlgorithm::Gzip),
+            "br" => Ok(CompressionAlgorithm::Br),
+            "deflate" => Ok(CompressionAlgorithm::Deflate),
+            "zstd" => Ok(CompressionAlgorithm::Zstd),
+            _ => Err(anyhow::anyhow!("Invalid compression algorithm")),
+        }
+    }
+}
+
+// 实现从CompressionAlgorithm枚举到字符串的转换
+impl From<CompressionAlgorithm> for &'static str {
+    fn from(algorithm: CompressionAlgorithm) -> Self {
+        match algorithm {
+            CompressionAlgorithm::Gzip => "gzip",
+            CompressionAlgorithm::Br => "br",
+            CompressionAlgorithm::Deflate => "deflate",
+            CompressionAlgorithm::Zstd => "zstd",
+        }
+    }
+}
+
+// 实现CompressionAlgorithm枚举的显示格式化
+impl fmt::Display for CompressionAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+// 自定义解析器，将字符串解析为AdminEndpoint枚举
+fn parse_admin_endpoint(endpoint: &str) -> Result<AdminEndpoint, anyhow::Error> {
+    endpoint.parse()
+}
+
+// 实现从字符串到AdminEndpoint枚举的转换
+impl FromStr for AdminEndpoint {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "health" => Ok(AdminEndpoint::Health),
+            "metrics" => Ok(AdminEndpoint::Metrics),
+            "info" => Ok(AdminEndpoint::Info),
+            _ => Err(anyhow::anyhow!("Invalid admin endpoint")),
+        }
+    }
+}
+
+// 实现从AdminEndpoint枚举到字符串的转换
+impl From<AdminEndpoint> for &'static str {
+    fn from(endpoint: AdminEndpoint) -> Self {
+        match endpoint {
+            AdminEndpoint::Health => "health",
+            AdminEndpoint::Metrics => "metrics",
+            AdminEndpoint::Info => "info",
+        }
+    }
+}
+
+// 实现AdminEndpoint枚举的显示格式化
+impl fmt::Display for AdminEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+// 自定义解析器，将字符串解析为AccessLogFormat枚举
+fn parse_access_log_format(format: &str) -> Result<AccessLogFormat, anyhow::Error> {
+    format.parse()
+}
+
+// 实现从字符串到AccessLogFormat枚举的转换
+impl FromStr for AccessLogFormat {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "common" => Ok(AccessLogFormat::Common),
+            "combined" => Ok(AccessLogFormat::Combined),
+            "json" => Ok(AccessLogFormat::Json),
+            _ => Err(anyhow::anyhow!("Invalid access log format")),
+        }
+    }
+}
+
+// 实现从AccessLogFormat枚举到字符串的转换
+impl From<AccessLogFormat> for &'static str {
+    fn from(format: AccessLogFormat) -> Self {
+        match format {
+            AccessLogFormat::Common => "common",
+            AccessLogFormat::Combined => "combined",
+            AccessLogFormat::Json => "json",
+        }
+    }
+}
+
+// 实现AccessLogFormat枚举的显示格式化
+impl fmt::Display for AccessLogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+// 自定义解析器，将请求方法转换为大写后解析
+fn parse_method(method: &str) -> Result<Method, anyhow::Error> {
+    Ok(Method::from_bytes(method.to_ascii_uppercase().as_bytes())?)
+}
+
+// 自定义解析器，将PREFIX=URL解析为ProxyRoute
+fn parse_proxy_route(route: &str) -> Result<ProxyRoute, anyhow::Error> {
+    route.parse()
+}
+
+impl FromStr for ProxyRoute {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (prefix, target) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Proxy route must be PREFIX=URL"))?;
+        let prefix = parse_prefix(prefix)?;
+        let target: axum::http::Uri = target.parse()?;
+        if target.scheme_str() != Some("http") || target.authority().is_none() {
+            return Err(anyhow::anyhow!("Proxy target must be an http:// URL"));
+        }
+        Ok(ProxyRoute {
+            prefix: prefix.to_string(),
+            target,
+        })
+    }
+}
+
+// 自定义解析器，将PREFIX=DIR解析为MountPoint
+fn parse_mount_point(mount: &str) -> Result<MountPoint, anyhow::Error> {
+    mount.parse()
+}
+
+impl FromStr for MountPoint {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (prefix, dir) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Mount must be PREFIX=DIR"))?;
+        Ok(MountPoint {
+            prefix: parse_prefix(prefix)?.to_string(),
+            dir: verify_path(dir).map_err(|e| anyhow::anyhow!("{}: {:?}", e, dir))?,
+        })
+    }
+}
+
+// 校验挂载点和代理的路径前缀：以/开头，去掉结尾的/后不能为空，且不能包含路由通配符
+fn parse_prefix(prefix: &str) -> Result<&str, anyhow::Error> {
+    let prefix = prefix.trim_end_matches('/');
+    if !prefix.starts_with('/') || prefix.contains(['{', '}', '*']) {
+        return Err(anyhow::anyhow!("Invalid path prefix: {:?}", prefix));
+    }
+    Ok(prefix)
+}
+
+// 自定义解析器，将N/s、N/m或N/h解析为RateLimit
+fn parse_rate_limit(limit: &str) -> Result<RateLimit, anyhow::Error> {
+    limit.parse()
+}
+
+impl FromStr for RateLimit {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (requests, unit) = s
+            .split_once('/')
+            .ok_or_else(|| anyhow::anyhow!("Rate limit must be N/s, N/m or N/h"))?;
+        let per = match unit {
+            "s" | "sec" => Duration::from_secs(1),
+            "m" | "min" => Duration::from_secs(60),
+            "h" | "hour" => Duration::from_secs(3600),
+            _ => return Err(anyhow::anyhow!("Invalid rate limit unit: {}", unit)),
+        };
+        let requests: u32 = requests.trim().parse()?;
+        if requests == 0 {
+            return Err(anyhow::anyhow!(
+                "Rate limit must allow at least one request"
+            ));
+        }
+        Ok(RateLimit { requests, per })
+    }
+}
+
+// 解析带有K/M/G后缀的大小（按1024进制），例如"512K"、"100M"、"2G"
+fn parse_size(size: &str) -> Result<u64, anyhow::Error> {
+    let size = size.trim();
+    let (num, unit) = match size.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => size.split_at(i),
+        None => (size, ""),
+    };
+    let multiplier: u64 = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        _ => return Err(anyhow::anyhow!("Invalid size unit: {}", unit)),
+    };
+    num.parse::<u64>()?
+        .checked_mul(multiplier)
+        .ok_or_else(|| anyhow::anyhow!("Size is too large"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024").unwrap(), 1024);
+        assert_eq!(parse_size("512K").unwrap(), 512 * 1024);
+        assert_eq!(parse_size("100m").unwrap(), 100 * 1024 * 1024);
+        assert_eq!(parse_size("2GiB").unwrap(), 2 * 1024 * 1024 * 1024);
+        assert!(parse_size("10X").is_err());
+        assert!(parse_size("M").is_err());
+    }
+
+    #[test]
+    fn test_parse_proxy_route() {
+        let route = parse_proxy_route("/api/=http://127.0.0.1:3000/v1").unwrap();
+        assert_eq!(route.prefix, "/api");
+        assert_eq!(route.target, "http://127.0.0.1:3000/v1");
+        assert!(parse_proxy_route("/=http://127.0.0.1:3000").is_err());
+        assert!(parse_proxy_route("api=http://127.0.0.1:3000").is_err());
+        assert!(parse_proxy_route("/api=https://example.com").is_err());
+        assert!(parse_proxy_route("/api").is_err());
+    }
+
+    #[test]
+    fn test_parse_rate_limit() {
+        let limit = parse_rate_limit("100/m").unwrap();
+        assert_eq!(limit.requests, 100);
+        assert_eq!(limit.per, Duration::from_secs(60));
+        assert!(parse_rate_limit("0/s").is_err());
+        assert!(parse_rate_limit("10/day").is_err());
+        assert!(parse_rate_limit("10").is_err());
+    }
+
+    #[test]
+    fn test_parse_mount_point() {
+        let mount = parse_mount_point("/static/=fixtures").unwrap();
+        assert_eq!(mount.prefix, "/static");
+        assert_eq!(mount.dir, PathBuf::from("fixtures"));
+        assert!(parse_mount_point("/static=no-such-dir").is_err());
+        assert!(parse_mount_point("/{x}=fixtures").is_err());
+        assert!(parse_mount_point("fixtures").is_err());
+    }
 }
@@ -8,8 +8,10 @@ use anyhow::Result;
 use std::path::{Path, PathBuf};
 
 pub use self::{
-    base64::Base64Format, base64::Base64SubCommand, csv::OutputFormat, http::HttpSubCommand,
-    text::TextSignFormat, text::TextSubCommand,
+    base64::Base64Format, base64::Base64SubCommand, csv::OutputFormat, http::AccessLogFormat,
+    http::AdminEndpoint, http::CompressionAlgorithm, http::HttpServeOpts, http::HttpSubCommand,
+    http::MountPoint, http::ProxyRoute, http::RateLimit, text::TextSignFormat,
+    text::TextSubCommand,
 };
 
 use crate::cli::csv::CsvOpts;
@@ -3,8 +3,9 @@ mod process;
 mod utils;
 
 pub use cli::{
-    Base64Format, Base64SubCommand, HttpSubCommand, Opts, SubCommand, TextSignFormat,
-    TextSubCommand,
+    AccessLogFormat, AdminEndpoint, Base64Format, Base64SubCommand, CompressionAlgorithm,
+    HttpServeOpts, HttpSubCommand, MountPoint, Opts, ProxyRoute, RateLimit, SubCommand,
+    TextSignFormat, TextSubCommand,
 };
 
 pub use process::*;
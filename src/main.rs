@@ -3,8 +3,9 @@ use std::fs;
 use clap::Parser;
 use rcli::{
     process_csv, process_decode, process_encode, process_generate_key, process_genpass,
-    process_http_serve, process_text_sign, process_text_verify, Base64SubCommand, HttpSubCommand,
-    Opts, SubCommand, TextSignFormat, TextSubCommand,
+    process_http_fetch, process_http_file, process_http_request, process_http_serve,
+    process_http_sign_url, process_text_sign, process_text_verify, Base64SubCommand,
+    HttpSubCommand, Opts, SubCommand, TextSignFormat, TextSubCommand,
 };
 use zxcvbn::zxcvbn;
 // rcli csv -i input.csv -o output.json --header -d ','
@@ -75,8 +76,45 @@ async fn main() -> anyhow::Result<()> {
         },
         SubCommand::Http(subcmd) => match subcmd {
             HttpSubCommand::Serve(opts) => {
-                process_http_serve(opts.dir, opts.port).await?;
+                process_http_serve(*opts).await?;
             }
+            HttpSubCommand::SignUrl(opts) => {
+                let url =
+                    process_http_sign_url(&opts.path, &opts.key, opts.expires, &opts.base_url)?;
+                println!("{}", url);
+            }
+            HttpSubCommand::Fetch(opts) => {
+                let summary =
+                    process_http_fetch(&opts.url, &opts.output, opts.verify.as_deref()).await?;
+                println!(
+                    "Downloaded {} file(s), {} unchanged",
+                    summary.downloaded, summary.unchanged
+                );
+            }
+            HttpSubCommand::Request(opts) => match (&opts.file, &opts.url) {
+                (Some(file), _) => {
+                    let report = process_http_file(file, &opts.var, opts.verbose).await?;
+                    print!("{}", report.output);
+                    println!("{} request(s), {} failed", report.total, report.failed);
+                    if report.failed > 0 {
+                        anyhow::bail!("{} request(s) failed", report.failed);
+                    }
+                }
+                (None, Some(url)) => {
+                    let output = process_http_request(
+                        opts.method,
+                        url,
+                        &opts.header,
+                        opts.json.as_deref(),
+                        &opts.form,
+                        opts.data.as_deref(),
+                    )
+                    .await?;
+                    println!("{}", output);
+                }
+                // clap保证url与file至少有一个
+                (None, None) => unreachable!(),
+            },
         },
     }
 
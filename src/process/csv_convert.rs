@@ -1,25 +1,8 @@
 use crate::cli::OutputFormat;
 use anyhow::Result;
-use csv::Reader;
-use serde::{Deserialize, Serialize};
+use csv::{Reader, StringRecord};
 use serde_json::Value;
-use std::fs;
-
-/// 表示球员的信息，包括姓名、位置、出生日期、国籍和球衣号码。
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(rename_all = "PascalCase")]
-struct Player {
-    // #[serde(rename = "Name")]
-    name: String,
-    // #[serde(rename = "Position")]
-    position: String,
-    #[serde(rename = "DOB")]
-    dob: String,
-    // #[serde(rename = "Nationality")]
-    nationality: String,
-    #[serde(rename = "Kit Number")]
-    kit: u8,
-}
+use std::{fs, io::Read};
 
 /// 处理CSV文件，将其内容转换为JSON格式并写入输出文件。
 ///
@@ -30,16 +13,12 @@ struct Player {
 /// # 返回值
 /// * `Result<()>` - 如果操作成功，返回Ok(())；否则返回错误。
 pub fn process_csv(input: &str, output: String, format: OutputFormat) -> Result<()> {
-    // 从输入文件路径创建CSV读取器
-    let mut reader = Reader::from_path(input)?;
+    // 读取表头与所有记录
+    let (headers, records) = read_csv(fs::File::open(input)?)?;
     // 预分配内存以提高性能
-    let mut ret = Vec::with_capacity(128);
-    // 获取CSV文件的表头
-    let headers = reader.headers()?.clone();
+    let mut ret = Vec::with_capacity(records.len());
     // 遍历CSV文件中的每一行记录
-    for result in reader.records() {
-        // 解析一行记录
-        let record = result?;
+    for record in records {
         // 将表头与记录数据组合成JSON对象
         // headers.iter() -> 使用 headers 的迭代器
         // record.iter()-> 使用 record 的迭代器
@@ -59,3 +38,20 @@ pub fn process_csv(input: &str, output: String, format: OutputFormat) -> Result<
     // 返回Ok表示函数成功完成
     Ok(())
 }
+
+/// 读取CSV内容，返回表头与所有记录。
+///
+/// # 参数
+/// * `input` - CSV内容的读取源，例如文件或字节切片。
+///
+/// # 返回值
+/// * `Result<(StringRecord, Vec<StringRecord>)>` - 表头与按顺序排列的记录；格式错误时返回错误。
+pub(crate) fn read_csv<R: Read>(input: R) -> Result<(StringRecord, Vec<StringRecord>)> {
+    // 从读取源创建CSV读取器
+    let mut reader = Reader::from_reader(input);
+    // 获取CSV文件的表头
+    let headers = reader.headers()?.clone();
+    // 解析所有记录，任何一行格式错误都返回错误
+    let records = reader.records().collect::<Result<Vec<_>, _>>()?;
+    Ok((headers, records))
+}
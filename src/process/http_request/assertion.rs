@@ -0,0 +1,245 @@
+use anyhow::{anyhow, bail, Result};
+use axum::http::{HeaderMap, StatusCode};
+use serde_json::Value;
+use std::{fmt, str::FromStr};
+
+// .http文件中??开头的断言，支持以下形式：
+// ?? status == 200
+// ?? header content-type contains json
+// ?? json $.items[0].name == "rcli"
+// ?? json $.id exists
+#[derive(Debug)]
+pub(super) struct Assertion {
+    // 断言的原始文本，用于输出结果
+    text: String,
+    target: Target,
+    op: Op,
+    expected: Value,
+}
+
+// 断言检查的对象
+#[derive(Debug, PartialEq)]
+enum Target {
+    Status,
+    Header(String),
+    Json(Vec<PathSegment>),
+}
+
+// JSON路径中的一段
+#[derive(Debug, PartialEq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+// 比较运算符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    Exists,
+}
+
+impl FromStr for Assertion {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || anyhow!("Invalid assertion {:?}", s);
+        let (kind, rest) = s.split_once(char::is_whitespace).ok_or_else(invalid)?;
+        let (target, rest) = match kind {
+            "status" => (Target::Status, rest.trim()),
+            "header" | "json" => {
+                let (arg, rest) = rest
+                    .trim()
+                    .split_once(char::is_whitespace)
+                    .ok_or_else(invalid)?;
+                let target = if kind == "header" {
+                    Target::Header(arg.to_ascii_lowercase())
+                } else {
+                    Target::Json(parse_json_path(arg)?)
+                };
+                (target, rest.trim())
+            }
+            _ => bail!("Invalid assertion {:?}, expected status, header or json", s),
+        };
+        let (op, expected) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let op: Op = op.parse()?;
+        let expected = expected.trim();
+        if op == Op::Exists {
+            if !expected.is_empty() || target == Target::Status {
+                return Err(invalid());
+            }
+        } else if expected.is_empty() {
+            return Err(invalid());
+        }
+        // 期望值按JSON解析，解析失败时作为字符串，头部的值始终是字符串
+        let expected = match target {
+            Target::Header(_) => Value::String(expected.trim_matches('"').to_string()),
+            _ => serde_json::from_str(expected).unwrap_or_else(|_| Value::String(expected.into())),
+        };
+        Ok(Self {
+            text: s.to_string(),
+            target,
+            op,
+            expected,
+        })
+    }
+}
+
+impl fmt::Display for Assertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+impl Assertion {
+    // 检查响应是否满足断言，不满足时返回实际的值
+    pub(super) fn check(
+        &self,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<(), String> {
+        let actual = match &self.target {
+            Target::Status => Some(Value::from(status.as_u16())),
+            Target::Header(name) => headers
+                .get(name.as_str())
+                .map(|v| Value::String(String::from_utf8_lossy(v.as_bytes()).into_owned())),
+            Target::Json(path) => {
+                let json: Value =
+                    serde_json::from_slice(body).map_err(|e| format!("body is not JSON: {}", e))?;
+                select(&json, path).cloned()
+            }
+        };
+        let Some(actual) = actual else {
+            return Err("missing".to_string());
+        };
+        if compare(self.op, &actual, &self.expected) {
+            Ok(())
+        } else {
+            Err(format!("got {}", actual))
+        }
+    }
+}
+
+impl FromStr for Op {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "==" => Ok(Op::Eq),
+            "!=" => Ok(Op::Ne),
+            "<" => Ok(Op::Lt),
+            "<=" => Ok(Op::Le),
+            ">" => Ok(Op::Gt),
+            ">=" => Ok(Op::Ge),
+            "contains" => Ok(Op::Contains),
+            "exists" => Ok(Op::Exists),
+            _ => Err(anyhow!("Invalid assertion operator {:?}", s)),
+        }
+    }
+}
+
+// 比较实际值与期望值，大小比较只对数字（或可以解析为数字的字符串）有效
+fn compare(op: Op, actual: &Value, expected: &Value) -> bool {
+    let number = |v: &Value| match v {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    };
+    let ordering = number(actual)
+        .zip(number(expected))
+        .and_then(|(a, e)| a.partial_cmp(&e));
+    match op {
+        Op::Exists => true,
+        Op::Eq => actual == expected || ordering.is_some_and(|o| o.is_eq()),
+        Op::Ne => actual != expected && !ordering.is_some_and(|o| o.is_eq()),
+        Op::Lt => ordering.is_some_and(|o| o.is_lt()),
+        Op::Le => ordering.is_some_and(|o| o.is_le()),
+        Op::Gt => ordering.is_some_and(|o| o.is_gt()),
+        Op::Ge => ordering.is_some_and(|o| o.is_ge()),
+        Op::Contains => match (actual, expected) {
+            (Value::String(a), Value::String(e)) => a.contains(e.as_str()),
+            (Value::String(a), e) => a.contains(&e.to_string()),
+            (Value::Array(items), e) => items.contains(e),
+            (Value::Object(map), Value::String(key)) => map.contains_key(key),
+            _ => false,
+        },
+    }
+}
+
+// 解析$.a.b[0]形式的JSON路径
+fn parse_json_path(path: &str) -> Result<Vec<PathSegment>> {
+    let invalid = || {
+        anyhow!(
+            "Invalid JSON path {:?}, expected e.g. $.items[0].name",
+            path
+        )
+    };
+    let mut rest = path.strip_prefix('$').ok_or_else(invalid)?;
+    let mut segments = Vec::new();
+    while !rest.is_empty() {
+        if let Some(index) = rest.strip_prefix('[') {
+            let (index, tail) = index.split_once(']').ok_or_else(invalid)?;
+            segments.push(PathSegment::Index(index.parse().map_err(|_| invalid())?));
+            rest = tail;
+        } else if let Some(key) = rest.strip_prefix('.') {
+            let end = key.find(['.', '[']).unwrap_or(key.len());
+            if end == 0 {
+                return Err(invalid());
+            }
+            segments.push(PathSegment::Key(key[..end].to_string()));
+            rest = &key[end..];
+        } else {
+            return Err(invalid());
+        }
+    }
+    Ok(segments)
+}
+
+// 按路径取出JSON中的值
+fn select<'a>(json: &'a Value, path: &[PathSegment]) -> Option<&'a Value> {
+    path.iter().try_fold(json, |value, segment| match segment {
+        PathSegment::Key(key) => value.get(key),
+        PathSegment::Index(index) => value.get(index),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::header;
+
+    #[test]
+    fn test_assertions() -> Result<()> {
+        let body = br#"{"name": "rcli", "items": [{"id": 1}, {"id": 2}], "tags": ["cli"]}"#;
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, "application/json".parse()?);
+        headers.insert(header::CONTENT_LENGTH, "64".parse()?);
+        let check = |s: &str| {
+            s.parse::<Assertion>()
+                .unwrap()
+                .check(StatusCode::OK, &headers, body)
+        };
+        assert_eq!(check("status == 200"), Ok(()));
+        assert_eq!(check("status >= 400"), Err("got 200".to_string()));
+        assert_eq!(check("header Content-Type contains json"), Ok(()));
+        assert_eq!(check("header content-length < 100"), Ok(()));
+        assert_eq!(check("header etag exists"), Err("missing".to_string()));
+        assert_eq!(check(r#"json $.name == "rcli""#), Ok(()));
+        assert_eq!(check("json $.name == rcli"), Ok(()));
+        assert_eq!(check("json $.items[1].id > 1"), Ok(()));
+        assert_eq!(check("json $.items[2] exists"), Err("missing".to_string()));
+        assert_eq!(check(r#"json $.tags contains "cli""#), Ok(()));
+        assert_eq!(check("json $.items[0].id != 1"), Err("got 1".to_string()));
+
+        assert!("status 200".parse::<Assertion>().is_err());
+        assert!("status exists".parse::<Assertion>().is_err());
+        assert!("json name == 1".parse::<Assertion>().is_err());
+        assert!("body == 1".parse::<Assertion>().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,292 @@
+use anyhow::{anyhow, bail, Context, Result};
+use axum::http::Method;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use super::{assertion::Assertion, parse_header_line, RequestSpec};
+
+// 变量引用其他变量时的最大展开深度，防止循环引用
+const MAX_DEPTH: usize = 16;
+
+// 解析后的.http文件，变量已经全部替换
+#[derive(Debug)]
+pub(super) struct HttpFile {
+    pub(super) requests: Vec<FileRequest>,
+}
+
+// .http文件中的一个请求
+#[derive(Debug)]
+pub(super) struct FileRequest {
+    // ###之后的标题，没有标题时为请求行
+    pub(super) name: String,
+    pub(super) method: Method,
+    pub(super) url: String,
+    headers: Vec<(String, String)>,
+    body: Option<FileBody>,
+    // ??开头的断言
+    pub(super) assertions: Vec<Assertion>,
+}
+
+// 请求体：直接写在文件中的内容，或者"< path"引用的文件
+#[derive(Debug, PartialEq)]
+enum FileBody {
+    Inline(String),
+    File(PathBuf),
+}
+
+// 变量替换之前的请求
+#[derive(Debug, Default)]
+struct RawRequest<'a> {
+    title: &'a str,
+    request_line: &'a str,
+    headers: Vec<&'a str>,
+    body: Vec<&'a str>,
+    assertions: Vec<&'a str>,
+}
+
+impl HttpFile {
+    // 按照REST Client的格式解析：请求之间以###分隔，@name = value定义变量，{{name}}引用变量
+    // dir为.http文件所在目录，"< path"形式的请求体相对于该目录；overrides覆盖文件中的同名变量
+    pub(super) fn parse(content: &str, dir: &Path, overrides: &[(String, String)]) -> Result<Self> {
+        let mut vars = HashMap::new();
+        let mut raw_requests = Vec::new();
+        let mut blocks = vec![("", Vec::new())];
+        for line in content.lines() {
+            match line.strip_prefix("###") {
+                Some(title) => blocks.push((title.trim(), Vec::new())),
+                None => blocks.last_mut().unwrap().1.push(line),
+            }
+        }
+        for (title, lines) in blocks {
+            if let Some(raw) = parse_block(title, &lines, &mut vars)? {
+                raw_requests.push(raw);
+            }
+        }
+        for (name, value) in overrides {
+            vars.insert(name.as_str(), value.as_str());
+        }
+
+        let requests = raw_requests
+            .into_iter()
+            .map(|raw| resolve(raw, dir, &vars))
+            .collect::<Result<_>>()?;
+        Ok(Self { requests })
+    }
+}
+
+impl FileRequest {
+    // 转换为待发送的请求，读取引用的请求体文件
+    pub(super) fn to_spec(&self) -> Result<RequestSpec> {
+        let body = match &self.body {
+            None => Vec::new(),
+            Some(FileBody::Inline(body)) => body.as_bytes().to_vec(),
+            Some(FileBody::File(path)) => {
+                std::fs::read(path).with_context(|| format!("Failed to read {:?}", path))?
+            }
+        };
+        Ok(RequestSpec {
+            method: self.method.clone(),
+            url: self.url.clone(),
+            headers: self.headers.clone(),
+            body,
+        })
+    }
+}
+
+// 解析一个###块：请求行之前可以有注释与变量定义，请求头之后空一行为请求体
+fn parse_block<'a>(
+    title: &'a str,
+    lines: &[&'a str],
+    vars: &mut HashMap<&'a str, &'a str>,
+) -> Result<Option<RawRequest<'a>>> {
+    let mut lines = lines.iter().map(|line| line.trim_end());
+    let mut raw = RawRequest {
+        title,
+        ..Default::default()
+    };
+    for line in lines.by_ref() {
+        let line = line.trim_start();
+        if line.is_empty() || is_comment(line) {
+            continue;
+        }
+        if let Some(var) = line.strip_prefix('@') {
+            let (name, value) = var
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid variable definition {:?}", line))?;
+            vars.insert(name.trim(), value.trim());
+            continue;
+        }
+        raw.request_line = line;
+        break;
+    }
+    if raw.request_line.is_empty() {
+        return Ok(None);
+    }
+    for line in lines.by_ref() {
+        let line = line.trim_start();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(assertion) = line.strip_prefix("??") {
+            raw.assertions.push(assertion.trim());
+        } else if !is_comment(line) {
+            raw.headers.push(line);
+        }
+    }
+    // 请求体中的??行同样作为断言
+    for line in lines {
+        match line.trim_start().strip_prefix("??") {
+            Some(assertion) => raw.assertions.push(assertion.trim()),
+            None => raw.body.push(line),
+        }
+    }
+    Ok(Some(raw))
+}
+
+// 替换变量并解析请求行、请求头与断言
+fn resolve(raw: RawRequest, dir: &Path, vars: &HashMap<&str, &str>) -> Result<FileRequest> {
+    let request_line = substitute(raw.request_line, vars, 0)?;
+    let mut tokens = request_line.split_whitespace();
+    let first = tokens.next().unwrap_or_default();
+    // 省略请求方法时默认为GET，请求行末尾的HTTP版本忽略
+    let (method, url) = match tokens.next() {
+        Some(url) if first.bytes().all(|b| b.is_ascii_uppercase()) => {
+            (Method::from_bytes(first.as_bytes())?, url)
+        }
+        _ => (Method::GET, first),
+    };
+    let headers = raw
+        .headers
+        .iter()
+        .map(|h| parse_header_line(&substitute(h, vars, 0)?))
+        .collect::<Result<_>>()?;
+    let assertions = raw
+        .assertions
+        .iter()
+        .map(|a| substitute(a, vars, 0)?.parse())
+        .collect::<Result<_>>()?;
+    let body = substitute(&raw.body.join("\n"), vars, 0)?;
+    let body = body.trim();
+    let body = if body.is_empty() {
+        None
+    } else {
+        match body.strip_prefix("< ") {
+            Some(path) if !path.contains('\n') => Some(FileBody::File(dir.join(path.trim()))),
+            _ => Some(FileBody::Inline(body.to_string())),
+        }
+    };
+    let name = if raw.title.is_empty() {
+        format!("{} {}", method, url)
+    } else {
+        raw.title.to_string()
+    };
+    Ok(FileRequest {
+        name,
+        method,
+        url: url.to_string(),
+        headers,
+        body,
+        assertions,
+    })
+}
+
+// 替换{{name}}形式的变量引用，变量的值中也可以引用其他变量
+fn substitute(s: &str, vars: &HashMap<&str, &str>, depth: usize) -> Result<String> {
+    if depth > MAX_DEPTH {
+        bail!("Variable expansion too deep in {:?}", s);
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + end].trim();
+        let value = vars
+            .get(name)
+            .ok_or_else(|| anyhow!("Undefined variable {{{{{}}}}}", name))?;
+        out.push_str(&rest[..start]);
+        out.push_str(&substitute(value, vars, depth + 1)?);
+        rest = &rest[start + end + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn is_comment(line: &str) -> bool {
+    line.starts_with('#') || line.starts_with("//")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_http_file() -> Result<()> {
+        let content = r#"
+@host = localhost:8080
+@base = http://{{host}}
+
+### get file
+GET {{base}}/Cargo.toml HTTP/1.1
+# 注释
+Range: bytes=0-99
+?? status == 206
+
+###
+// 省略请求方法
+{{base}}/fixtures
+
+### echo
+POST {{base}}/_echo
+Content-Type: application/json
+
+{"name": "{{name}}"}
+
+?? json $.body.name == "rcli"
+
+### upload
+PUT {{base}}/upload.txt
+
+< ./fixtures/b64.txt
+"#;
+        let overrides = [("name".to_string(), "rcli".to_string())];
+        let file = HttpFile::parse(content, Path::new("/tmp"), &overrides)?;
+        let names: Vec<_> = file.requests.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "get file",
+                "GET http://localhost:8080/fixtures",
+                "echo",
+                "upload"
+            ]
+        );
+        let get = &file.requests[0];
+        assert_eq!(get.url, "http://localhost:8080/Cargo.toml");
+        assert_eq!(
+            get.headers,
+            vec![("Range".to_string(), "bytes=0-99".to_string())]
+        );
+        assert_eq!(get.assertions.len(), 1);
+        assert!(get.body.is_none());
+
+        let echo = &file.requests[2];
+        assert_eq!(echo.method, Method::POST);
+        assert_eq!(
+            echo.body,
+            Some(FileBody::Inline(r#"{"name": "rcli"}"#.to_string()))
+        );
+        assert_eq!(echo.assertions.len(), 1);
+        assert_eq!(
+            file.requests[3].body,
+            Some(FileBody::File(PathBuf::from("/tmp/./fixtures/b64.txt")))
+        );
+
+        let err = HttpFile::parse("GET http://{{missing}}/", Path::new("."), &[]).unwrap_err();
+        assert_eq!(err.to_string(), "Undefined variable {{missing}}");
+        Ok(())
+    }
+}
@@ -0,0 +1,474 @@
+mod assertion;
+mod http_file;
+
+use anyhow::{anyhow, Context, Result};
+use axum::{
+    body::{Body, Bytes},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, Request, StatusCode, Version},
+};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use hyper_util::{
+    client::legacy::{connect::HttpConnector, Client},
+    rt::TokioExecutor,
+};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use rand::Rng;
+use std::{
+    fmt::Write as _,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use http_file::HttpFile;
+
+// application/x-www-form-urlencoded中不需要编码的字符
+const FORM_VALUE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'*');
+
+pub(crate) type HttpClient = Client<HttpsConnector<HttpConnector>, Body>;
+
+// 待发送的请求
+#[derive(Debug, Default)]
+struct RequestSpec {
+    method: Method,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+// 收到的响应，响应体完整读入内存
+#[derive(Debug)]
+struct Exchange {
+    version: Version,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    elapsed: Duration,
+}
+
+// 执行.http文件的结果
+#[derive(Debug)]
+pub struct HttpFileReport {
+    // 每个请求的执行结果与断言结果
+    pub output: String,
+    // 执行的请求数
+    pub total: usize,
+    // 请求失败或断言不通过的请求数
+    pub failed: usize,
+}
+
+// 发送单个请求，返回状态行、响应头与响应体，JSON响应体会被格式化
+pub async fn process_http_request(
+    method: Method,
+    url: &str,
+    headers: &[String],
+    json: Option<&str>,
+    form: &[String],
+    data: Option<&str>,
+) -> Result<String> {
+    let mut spec = RequestSpec {
+        method,
+        url: url.to_string(),
+        headers: headers
+            .iter()
+            .map(|h| parse_header_line(h))
+            .collect::<Result<_>>()?,
+        ..Default::default()
+    };
+    if let Some(json) = json {
+        let body = read_arg(json)?;
+        serde_json::from_slice::<serde_json::Value>(&body).context("Invalid JSON body")?;
+        spec.set_default_header("content-type", "application/json");
+        spec.body = body;
+    } else if !form.is_empty() {
+        let fields = form
+            .iter()
+            .map(|f| {
+                f.split_once('=')
+                    .ok_or_else(|| anyhow!("Invalid form field {:?}, expected KEY=VALUE", f))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let (content_type, body) = encode_form(&fields)?;
+        spec.set_default_header("content-type", &content_type);
+        spec.body = body;
+    } else if let Some(data) = data {
+        spec.body = read_arg(data)?;
+    }
+    let exchange = send(&client()?, &spec).await?;
+    Ok(format_response(&exchange))
+}
+
+// 按顺序执行.http文件中的请求，单个请求失败不影响后续请求
+pub async fn process_http_file(
+    path: &str,
+    vars: &[String],
+    verbose: bool,
+) -> Result<HttpFileReport> {
+    let content = std::fs::read_to_string(path)?;
+    let overrides = vars
+        .iter()
+        .map(|v| {
+            v.split_once('=')
+                .map(|(name, value)| (name.trim().to_string(), value.to_string()))
+                .ok_or_else(|| anyhow!("Invalid variable {:?}, expected NAME=VALUE", v))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let dir = Path::new(path).parent().unwrap_or(Path::new("."));
+    let file = HttpFile::parse(&content, dir, &overrides)?;
+    let client = client()?;
+
+    let mut report = HttpFileReport {
+        output: String::new(),
+        total: file.requests.len(),
+        failed: 0,
+    };
+    let out = &mut report.output;
+    for request in &file.requests {
+        writeln!(out, "### {}", request.name)?;
+        let result = match request.to_spec() {
+            Ok(spec) => send(&client, &spec).await,
+            Err(e) => Err(e),
+        };
+        let exchange = match result {
+            Ok(exchange) => exchange,
+            Err(e) => {
+                writeln!(
+                    out,
+                    "{} {} -> FAILED: {:#}\n",
+                    request.method, request.url, e
+                )?;
+                report.failed += 1;
+                continue;
+            }
+        };
+        writeln!(
+            out,
+            "{} {} -> {} ({} ms)",
+            request.method,
+            request.url,
+            exchange.status,
+            exchange.elapsed.as_millis()
+        )?;
+        let mut passed = true;
+        for assertion in &request.assertions {
+            match assertion.check(exchange.status, &exchange.headers, &exchange.body) {
+                Ok(()) => writeln!(out, "  ok: {}", assertion)?,
+                Err(reason) => {
+                    passed = false;
+                    writeln!(out, "  FAILED: {} ({})", assertion, reason)?;
+                }
+            }
+        }
+        if verbose {
+            writeln!(out, "{}", format_response(&exchange))?;
+        }
+        writeln!(out)?;
+        if !passed {
+            report.failed += 1;
+        }
+    }
+    Ok(report)
+}
+
+impl RequestSpec {
+    // 未设置同名请求头时才添加
+    fn set_default_header(&mut self, name: &str, value: &str) {
+        if !self
+            .headers
+            .iter()
+            .any(|(n, _)| n.eq_ignore_ascii_case(name))
+        {
+            self.headers.push((name.to_string(), value.to_string()));
+        }
+    }
+}
+
+// 创建同时支持http与https的客户端，https使用内置的webpki根证书
+pub(crate) fn client() -> Result<HttpClient> {
+    let connector = HttpsConnectorBuilder::new()
+        .with_provider_and_webpki_roots(rustls::crypto::ring::default_provider())?
+        .https_or_http()
+        .enable_http1()
+        .build();
+    Ok(Client::builder(TokioExecutor::new()).build(connector))
+}
+
+// 发送请求并读取完整的响应体
+async fn send(client: &HttpClient, spec: &RequestSpec) -> Result<Exchange> {
+    let mut builder = Request::builder()
+        .method(spec.method.clone())
+        .uri(&spec.url);
+    for (name, value) in &spec.headers {
+        builder = builder.header(
+            HeaderName::from_bytes(name.as_bytes())?,
+            HeaderValue::from_str(value)?,
+        );
+    }
+    let req = builder.body(Body::from(spec.body.clone()))?;
+    let start = Instant::now();
+    let res = client
+        .request(req)
+        .await
+        .with_context(|| format!("Failed to send request to {}", spec.url))?;
+    let (parts, body) = res.into_parts();
+    let body = axum::body::to_bytes(Body::new(body), usize::MAX).await?;
+    Ok(Exchange {
+        version: parts.version,
+        status: parts.status,
+        headers: parts.headers,
+        body,
+        elapsed: start.elapsed(),
+    })
+}
+
+// 输出状态行、响应头与响应体，非UTF-8的响应体只输出长度
+fn format_response(exchange: &Exchange) -> String {
+    let mut out = format!("{:?} {}\n", exchange.version, exchange.status);
+    for (name, value) in &exchange.headers {
+        let _ = writeln!(
+            out,
+            "{}: {}",
+            name,
+            String::from_utf8_lossy(value.as_bytes())
+        );
+    }
+    out.push('\n');
+    let is_json = exchange
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("json"));
+    let pretty = is_json
+        .then(|| serde_json::from_slice::<serde_json::Value>(&exchange.body).ok())
+        .flatten()
+        .and_then(|v| serde_json::to_string_pretty(&v).ok());
+    match (pretty, std::str::from_utf8(&exchange.body)) {
+        (Some(pretty), _) => out.push_str(&pretty),
+        (None, Ok(text)) => out.push_str(text),
+        (None, Err(_)) => {
+            let _ = write!(out, "[{} bytes of binary data]", exchange.body.len());
+        }
+    }
+    out
+}
+
+// 解析"Name: value"格式的请求头
+fn parse_header_line(line: &str) -> Result<(String, String)> {
+    let (name, value) = line
+        .split_once(':')
+        .ok_or_else(|| anyhow!("Invalid header {:?}, expected \"Name: value\"", line))?;
+    Ok((name.trim().to_string(), value.trim().to_string()))
+}
+
+// 以@开头的参数表示文件路径，读取文件内容
+fn read_arg(arg: &str) -> Result<Vec<u8>> {
+    match arg.strip_prefix('@') {
+        Some(path) => std::fs::read(path).with_context(|| format!("Failed to read {}", path)),
+        None => Ok(arg.as_bytes().to_vec()),
+    }
+}
+
+// 编码表单字段，存在@文件字段时使用multipart/form-data，否则使用urlencoded
+fn encode_form(fields: &[(&str, &str)]) -> Result<(String, Vec<u8>)> {
+    if !fields.iter().any(|(_, value)| value.starts_with('@')) {
+        let body = fields
+            .iter()
+            .map(|(key, value)| {
+                format!(
+                    "{}={}",
+                    utf8_percent_encode(key, FORM_VALUE),
+                    utf8_percent_encode(value, FORM_VALUE)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+        return Ok((
+            "application/x-www-form-urlencoded".to_string(),
+            body.into_bytes(),
+        ));
+    }
+    let boundary = format!("rcli-{:016x}", rand::thread_rng().gen::<u64>());
+    let mut body = Vec::new();
+    for (key, value) in fields {
+        body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+        let name = key.replace('"', "%22");
+        match value.strip_prefix('@') {
+            Some(path) => {
+                let filename = Path::new(path)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().replace('"', "%22"))
+                    .unwrap_or_default();
+                let mime = mime_guess::from_path(path).first_or_octet_stream();
+                body.extend_from_slice(
+                    format!(
+                        "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                        name, filename, mime
+                    )
+                    .as_bytes(),
+                );
+                body.extend_from_slice(&read_arg(value)?);
+            }
+            None => {
+                body.extend_from_slice(
+                    format!("Content-Disposition: form-data; name=\"{}\"\r\n\r\n", name).as_bytes(),
+                );
+                body.extend_from_slice(value.as_bytes());
+            }
+        }
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+    Ok((format!("multipart/form-data; boundary={}", boundary), body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Json, Router};
+
+    // 在随机端口上启动把请求体原样返回的服务，返回服务地址
+    async fn spawn_echo() -> Result<String> {
+        let router = Router::new().route(
+            "/echo",
+            post(|body: String| async move { Json(serde_json::json!({ "body": body })) }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        Ok(format!("127.0.0.1:{}", addr.port()))
+    }
+
+    // 在空闲端口上启动rcli http serve，返回服务地址
+    async fn spawn_serve(dir: &str) -> Result<String> {
+        use clap::Parser;
+        let port = std::net::TcpListener::bind("127.0.0.1:0")?
+            .local_addr()?
+            .port();
+        let opts = crate::HttpServeOpts::parse_from([
+            "serve",
+            "--bind",
+            "127.0.0.1",
+            "--port",
+            &port.to_string(),
+            "--dir",
+            dir,
+            "--echo",
+        ]);
+        tokio::spawn(crate::process_http_serve(opts));
+        let addr = format!("127.0.0.1:{}", port);
+        for _ in 0..100 {
+            if tokio::net::TcpStream::connect(&addr).await.is_ok() {
+                return Ok(addr);
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        Err(anyhow!("rcli http serve did not start on {}", addr))
+    }
+
+    #[tokio::test]
+    async fn test_http_file_against_serve() -> Result<()> {
+        let host = spawn_serve("fixtures").await?;
+        let tmp = tempfile::tempdir()?;
+        let path = tmp.path().join("serve.http");
+        std::fs::write(
+            &path,
+            r#"
+### echo
+POST http://{{host}}/_echo?name=rcli
+Content-Type: application/json
+X-Test: yes
+
+{"hello": "{{name}}"}
+
+?? status == 200
+?? header content-type contains json
+?? json $.method == "POST"
+?? json $.query.name == "rcli"
+?? json $.headers.x-test == "yes"
+?? json $.body == "{\"hello\": \"rcli\"}"
+
+### static file
+GET http://{{host}}/b64.txt
+
+?? status == 200
+?? header etag exists
+?? header content-type contains text/plain
+
+### missing
+GET http://{{host}}/missing.txt
+
+?? status == 200
+"#,
+        )?;
+        let vars = [format!("host={}", host), "name=rcli".to_string()];
+        let report = process_http_file(path.to_str().unwrap(), &vars, false).await?;
+        assert_eq!(report.total, 3, "{}", report.output);
+        assert_eq!(report.failed, 1, "{}", report.output);
+        assert!(report
+            .output
+            .contains(r#"  ok: json $.headers.x-test == "yes""#));
+        assert!(report.output.contains("  ok: header etag exists"));
+        assert!(report.output.contains("  FAILED: status == 200 (got 404)"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_process_http_file() -> Result<()> {
+        let host = spawn_echo().await?;
+        let tmp = tempfile::tempdir()?;
+        std::fs::write(tmp.path().join("body.txt"), "from file")?;
+        let path = tmp.path().join("test.http");
+        std::fs::write(
+            &path,
+            r#"
+@host = localhost
+
+### inline
+POST http://{{host}}/echo
+
+hello {{name}}
+
+?? status == 200
+?? json $.body == "hello rcli"
+
+### file
+POST http://{{host}}/echo
+
+< body.txt
+
+?? json $.body == "wrong"
+
+### missing
+GET http://{{host}}/missing
+?? status == 404
+"#,
+        )?;
+        let vars = [format!("host={}", host), "name=rcli".to_string()];
+        let report = process_http_file(path.to_str().unwrap(), &vars, false).await?;
+        assert_eq!(report.total, 3);
+        assert_eq!(report.failed, 1);
+        assert!(report
+            .output
+            .contains(r#"  ok: json $.body == "hello rcli""#));
+        assert!(report
+            .output
+            .contains(r#"  FAILED: json $.body == "wrong" (got "from file")"#));
+        assert!(report.output.contains("-> 404 Not Found"));
+
+        let output = process_http_request(
+            Method::POST,
+            &format!("http://{}/echo", host),
+            &[],
+            None,
+            &["a=1 2".to_string(), "b=&".to_string()],
+            None,
+        )
+        .await?;
+        assert!(output.starts_with("HTTP/1.1 200 OK\n"));
+        assert!(output.ends_with("{\n  \"body\": \"a=1%202&b=%26\"\n}"));
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+use anyhow::Result;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Local};
+use serde_json::json;
+use std::{
+    fmt,
+    fs::OpenOptions,
+    io::Write,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+use tracing::warn;
+
+use super::ServerState;
+use crate::AccessLogFormat;
+
+// 访问日志写入器，输出到标准输出或追加到文件
+pub(super) struct AccessLog {
+    // 日志格式
+    format: AccessLogFormat,
+    // 日志输出目标
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl fmt::Debug for AccessLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccessLog")
+            .field("format", &self.format)
+            .finish_non_exhaustive()
+    }
+}
+
+// 一次请求的访问记录
+struct AccessEntry<'a> {
+    time: DateTime<Local>,
+    remote: Option<SocketAddr>,
+    method: &'a str,
+    uri: &'a str,
+    version: String,
+    status: u16,
+    bytes: Option<u64>,
+    referer: Option<&'a str>,
+    user_agent: Option<&'a str>,
+    latency_ms: f64,
+}
+
+impl AccessLog {
+    // 创建访问日志，target为"-"时输出到标准输出，否则追加写入文件
+    pub(super) fn try_new(target: &str, format: AccessLogFormat) -> Result<Self> {
+        let writer: Box<dyn Write + Send> = if target == "-" {
+            Box::new(std::io::stdout())
+        } else {
+            Box::new(OpenOptions::new().create(true).append(true).open(target)?)
+        };
+        Ok(Self {
+            format,
+            writer: Mutex::new(writer),
+        })
+    }
+
+    // 按照配置的格式写入一条记录
+    fn write(&self, entry: &AccessEntry) {
+        let line = match self.format {
+            AccessLogFormat::Common => entry.common(),
+            AccessLogFormat::Combined => format!(
+                "{} \"{}\" \"{}\"",
+                entry.common(),
+                entry.referer.unwrap_or("-"),
+                entry.user_agent.unwrap_or("-")
+            ),
+            AccessLogFormat::Json => entry.json(),
+        };
+        if let Ok(mut writer) = self.writer.lock() {
+            if let Err(e) = writeln!(writer, "{}", line).and_then(|_| writer.flush()) {
+                warn!("Error writing access log: {:?}", e);
+            }
+        }
+    }
+}
+
+impl AccessEntry<'_> {
+    // Common Log Format: host ident user [time] "request" status bytes
+    fn common(&self) -> String {
+        format!(
+            "{} - - [{}] \"{} {} {}\" {} {}",
+            self.remote
+                .map(|addr| addr.ip().to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            self.time.format("%d/%b/%Y:%H:%M:%S %z"),
+            self.method,
+            self.uri,
+            self.version,
+            self.status,
+            self.bytes
+                .map(|b| b.to_string())
+                .unwrap_or_else(|| "-".to_string())
+        )
+    }
+
+    // 每行一个JSON对象
+    fn json(&self) -> String {
+        json!({
+            "time": self.time.to_rfc3339(),
+            "remote": self.remote.map(|addr| addr.ip().to_string()),
+            "method": self.method,
+            "uri": self.uri,
+            "version": self.version,
+            "status": self.status,
+            "bytes": self.bytes,
+            "referer": self.referer,
+            "user_agent": self.user_agent,
+            "latency_ms": self.latency_ms,
+        })
+        .to_string()
+    }
+}
+
+// 访问日志中间件，在响应生成后记录一条访问记录
+pub(super) async fn log_access(
+    State(state): State<Arc<ServerState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let Some(access_log) = &state.access_log else {
+        return next.run(req).await;
+    };
+    let start = Instant::now();
+    let time = Local::now();
+    let remote = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| *addr);
+    let method = req.method().clone();
+    let uri = req.uri().clone();
+    let version = format!("{:?}", req.version());
+    let headers = req.headers().clone();
+    let res = next.run(req).await;
+    let entry = AccessEntry {
+        time,
+        remote,
+        method: method.as_str(),
+        uri: &uri.to_string(),
+        version,
+        status: res.status().as_u16(),
+        bytes: content_length(res.headers()),
+        referer: header_str(&headers, header::REFERER),
+        user_agent: header_str(&headers, header::USER_AGENT),
+        latency_ms: start.elapsed().as_secs_f64() * 1000.0,
+    };
+    access_log.write(&entry);
+    res
+}
+
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    header_str(headers, header::CONTENT_LENGTH).and_then(|v| v.parse().ok())
+}
+
+fn header_str(headers: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::test_util::test_router;
+    use anyhow::Result;
+    use axum::{
+        body::Body,
+        http::{header, Request},
+    };
+    use chrono::TimeZone;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_access_entry_format() {
+        let entry = AccessEntry {
+            time: Local.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap(),
+            remote: Some("127.0.0.1:5000".parse().unwrap()),
+            method: "GET",
+            uri: "/index.html",
+            version: "HTTP/1.1".to_string(),
+            status: 200,
+            bytes: Some(42),
+            referer: None,
+            user_agent: Some("curl/8.0"),
+            latency_ms: 1.5,
+        };
+        let common = entry.common();
+        assert!(common.starts_with("127.0.0.1 - - [02/Jan/2024:03:04:05 "));
+        assert!(common.ends_with("] \"GET /index.html HTTP/1.1\" 200 42"));
+        let json: serde_json::Value = serde_json::from_str(&entry.json()).unwrap();
+        assert_eq!(json["status"], 200);
+        assert_eq!(json["user_agent"], "curl/8.0");
+    }
+
+    #[tokio::test]
+    async fn test_cors_and_access_log() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let log = tmp.path().join("access.log");
+        let router = test_router(&[
+            "--dir",
+            "fixtures",
+            "--cors",
+            "--cors-origin",
+            "https://example.com",
+            "--access-log",
+            log.to_str().unwrap(),
+            "--access-log-format",
+            "json",
+        ]);
+        let req = Request::get("/b64.txt")
+            .header(header::ORIGIN, "https://example.com")
+            .body(Body::empty())?;
+        let res = router.oneshot(req).await?;
+        assert_eq!(
+            res.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://example.com"
+        );
+        let line = std::fs::read_to_string(&log)?;
+        let entry: serde_json::Value = serde_json::from_str(line.trim())?;
+        assert_eq!(entry["uri"], "/b64.txt");
+        assert_eq!(entry["status"], 200);
+        Ok(())
+    }
+}
@@ -0,0 +1,399 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{MatchedPath, Request, State},
+    http::{header, Method},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+    routing::get,
+    Router,
+};
+use http_body::{Frame, SizeHint};
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use super::ServerState;
+use crate::AdminEndpoint;
+
+// 延迟直方图各个桶的上限（秒）
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 10.0,
+];
+
+// 按方法和路由统计的请求指标
+#[derive(Debug, Default)]
+pub(super) struct Metrics {
+    routes: Mutex<BTreeMap<RouteKey, RouteStats>>,
+}
+
+// 请求方法与匹配的路由模式
+type RouteKey = (String, String);
+
+// 单个路由的统计数据
+#[derive(Debug, Default)]
+struct RouteStats {
+    // 各状态码的请求数
+    statuses: BTreeMap<u16, u64>,
+    // 已发送的响应体字节数
+    bytes: u64,
+    // 延迟落在各个桶中的请求数（非累计）
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    // 延迟总和（秒）
+    latency_sum: f64,
+    // 请求总数
+    count: u64,
+}
+
+impl Metrics {
+    // 记录一次请求的状态码与生成响应头的耗时
+    fn record(&self, key: &RouteKey, status: u16, latency: f64) {
+        let Ok(mut routes) = self.routes.lock() else {
+            return;
+        };
+        let stats = routes.entry(key.clone()).or_default();
+        *stats.statuses.entry(status).or_default() += 1;
+        if let Some(i) = LATENCY_BUCKETS.iter().position(|&le| latency <= le) {
+            stats.buckets[i] += 1;
+        }
+        stats.latency_sum += latency;
+        stats.count += 1;
+    }
+
+    // 记录响应体发送的字节数
+    fn add_bytes(&self, key: &RouteKey, bytes: u64) {
+        if let Ok(mut routes) = self.routes.lock() {
+            routes.entry(key.clone()).or_default().bytes += bytes;
+        }
+    }
+
+    // 以Prometheus文本格式输出所有指标
+    fn render(&self, uptime: f64) -> String {
+        let mut out = String::new();
+        let Ok(routes) = self.routes.lock() else {
+            return out;
+        };
+        out.push_str("# HELP rcli_http_requests_total Total number of HTTP requests.\n");
+        out.push_str("# TYPE rcli_http_requests_total counter\n");
+        for ((method, route), stats) in routes.iter() {
+            for (status, count) in &stats.statuses {
+                let _ = writeln!(
+                    out,
+                    "rcli_http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+                    escape(method),
+                    escape(route),
+                    status,
+                    count
+                );
+            }
+        }
+        out.push_str(
+            "# HELP rcli_http_response_bytes_total Total number of response body bytes sent.\n",
+        );
+        out.push_str("# TYPE rcli_http_response_bytes_total counter\n");
+        for ((method, route), stats) in routes.iter() {
+            let _ = writeln!(
+                out,
+                "rcli_http_response_bytes_total{{method=\"{}\",route=\"{}\"}} {}",
+                escape(method),
+                escape(route),
+                stats.bytes
+            );
+        }
+        out.push_str(
+            "# HELP rcli_http_request_duration_seconds Time taken to produce the response headers.\n",
+        );
+        out.push_str("# TYPE rcli_http_request_duration_seconds histogram\n");
+        for ((method, route), stats) in routes.iter() {
+            let labels = format!("method=\"{}\",route=\"{}\"", escape(method), escape(route));
+            let mut cumulative = 0;
+            for (le, count) in LATENCY_BUCKETS.iter().zip(stats.buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "rcli_http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, le, cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "rcli_http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, stats.count
+            );
+            let _ = writeln!(
+                out,
+                "rcli_http_request_duration_seconds_sum{{{}}} {}",
+                labels, stats.latency_sum
+            );
+            let _ = writeln!(
+                out,
+                "rcli_http_request_duration_seconds_count{{{}}} {}",
+                labels, stats.count
+            );
+        }
+        out.push_str("# HELP rcli_uptime_seconds Seconds since the server started.\n");
+        out.push_str("# TYPE rcli_uptime_seconds gauge\n");
+        let _ = writeln!(out, "rcli_uptime_seconds {}", uptime);
+        out
+    }
+}
+
+// 转义Prometheus标签值中的反斜杠、引号与换行
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// 统计发送字节数的响应体，响应结束或连接中断时记录
+struct CountingBody {
+    inner: Body,
+    metrics: Arc<Metrics>,
+    key: RouteKey,
+    bytes: u64,
+}
+
+impl http_body::Body for CountingBody {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let poll = Pin::new(&mut self.inner).poll_frame(cx);
+        if let Poll::Ready(Some(Ok(frame))) = &poll {
+            if let Some(data) = frame.data_ref() {
+                self.bytes += data.len() as u64;
+            }
+        }
+        poll
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl Drop for CountingBody {
+    fn drop(&mut self) {
+        self.metrics.add_bytes(&self.key, self.bytes);
+    }
+}
+
+// 指标采集中间件，按请求方法与匹配的路由模式统计
+pub(super) async fn track_metrics(
+    State(metrics): State<Arc<Metrics>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let key = (method_label(req.method()).to_string(), route);
+    let start = Instant::now();
+    let res = next.run(req).await;
+    metrics.record(&key, res.status().as_u16(), start.elapsed().as_secs_f64());
+    let (parts, body) = res.into_parts();
+    let body = CountingBody {
+        inner: body,
+        metrics,
+        key,
+        bytes: 0,
+    };
+    Response::from_parts(parts, Body::new(body))
+}
+
+// 指标中的方法标签，标准方法与WebDAV方法之外的扩展方法统一记为OTHER，避免指标数量无限增长
+fn method_label(method: &Method) -> &'static str {
+    match method.as_str() {
+        "GET" => "GET",
+        "HEAD" => "HEAD",
+        "POST" => "POST",
+        "PUT" => "PUT",
+        "DELETE" => "DELETE",
+        "CONNECT" => "CONNECT",
+        "OPTIONS" => "OPTIONS",
+        "TRACE" => "TRACE",
+        "PATCH" => "PATCH",
+        "PROPFIND" => "PROPFIND",
+        "PROPPATCH" => "PROPPATCH",
+        "MKCOL" => "MKCOL",
+        "COPY" => "COPY",
+        "MOVE" => "MOVE",
+        "LOCK" => "LOCK",
+        "UNLOCK" => "UNLOCK",
+        _ => "OTHER",
+    }
+}
+
+// 创建包含已启用管理端点的路由
+pub(super) fn admin_router(state: Arc<ServerState>) -> Router {
+    let mut router = Router::new();
+    for endpoint in &state.admin {
+        router = match endpoint {
+            AdminEndpoint::Health => router.route("/_health", get(health_handler)),
+            AdminEndpoint::Metrics => router.route("/_metrics", get(metrics_handler)),
+            AdminEndpoint::Info => router.route("/_info", get(info_handler)),
+        };
+    }
+    router.with_state(state)
+}
+
+// 存活检查
+async fn health_handler() -> Response {
+    Json(json!({ "status": "ok" })).into_response()
+}
+
+// Prometheus格式的指标
+async fn metrics_handler(State(state): State<Arc<ServerState>>) -> Response {
+    let uptime = state.started.elapsed().as_secs_f64();
+    let body = state
+        .metrics
+        .as_ref()
+        .map(|metrics| metrics.render(uptime))
+        .unwrap_or_default();
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        body,
+    )
+        .into_response()
+}
+
+// 服务信息：版本、挂载的目录与运行时间
+async fn info_handler(State(state): State<Arc<ServerState>>) -> Response {
+    let mounts: Vec<_> = state
+        .mounts
+        .iter()
+        .map(|m| json!({ "prefix": format!("{}/", m.base), "path": m.path }))
+        .collect();
+    Json(json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "root": state.mounts[0].path,
+        "mounts": mounts,
+        "started_at": state.started_at.to_rfc3339(),
+        "uptime_secs": state.started.elapsed().as_secs(),
+    }))
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::test_util::{get_status, test_router};
+    use anyhow::Result;
+    use axum::{
+        body::to_bytes,
+        http::{Request, StatusCode},
+    };
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_render_metrics() {
+        let metrics = Metrics::default();
+        let key = ("GET".to_string(), "/{*path}".to_string());
+        metrics.record(&key, 200, 0.003);
+        metrics.record(&key, 404, 0.2);
+        metrics.add_bytes(&key, 42);
+        let out = metrics.render(1.5);
+        assert!(out.contains(
+            "rcli_http_requests_total{method=\"GET\",route=\"/{*path}\",status=\"200\"} 1"
+        ));
+        assert!(
+            out.contains("rcli_http_response_bytes_total{method=\"GET\",route=\"/{*path}\"} 42")
+        );
+        assert!(out.contains(
+            "rcli_http_request_duration_seconds_bucket{method=\"GET\",route=\"/{*path}\",le=\"0.005\"} 1"
+        ));
+        assert!(out.contains(
+            "rcli_http_request_duration_seconds_bucket{method=\"GET\",route=\"/{*path}\",le=\"0.25\"} 2"
+        ));
+        assert!(out.contains(
+            "rcli_http_request_duration_seconds_count{method=\"GET\",route=\"/{*path}\"} 2"
+        ));
+        assert!(out.contains("rcli_uptime_seconds 1.5"));
+        assert_eq!(escape("a\"b\\c"), "a\\\"b\\\\c");
+
+        assert_eq!(method_label(&Method::GET), "GET");
+        assert_eq!(
+            method_label(&Method::from_bytes(b"PROPFIND").unwrap()),
+            "PROPFIND"
+        );
+        assert_eq!(method_label(&Method::from_bytes(b"FOO").unwrap()), "OTHER");
+        assert_eq!(method_label(&Method::from_bytes(b"get").unwrap()), "OTHER");
+    }
+
+    #[tokio::test]
+    async fn test_admin_endpoints() -> Result<()> {
+        let router = test_router(&[
+            "--dir",
+            "fixtures",
+            "--admin",
+            "health,metrics,info",
+            "--echo",
+        ]);
+        assert_eq!(get_status(&router, "/_health").await, StatusCode::OK);
+        let res = router
+            .clone()
+            .oneshot(Request::get("/b64.txt").body(Body::empty())?)
+            .await?;
+        let len = to_bytes(res.into_body(), usize::MAX).await?.len();
+        // 扩展方法统一记为OTHER
+        for method in ["BREW", "WHEN"] {
+            let req = Request::builder()
+                .method(method)
+                .uri("/_echo")
+                .body(Body::empty())?;
+            router.clone().oneshot(req).await?;
+        }
+
+        let res = router
+            .clone()
+            .oneshot(Request::get("/_metrics").body(Body::empty())?)
+            .await?;
+        let metrics = String::from_utf8(to_bytes(res.into_body(), usize::MAX).await?.to_vec())?;
+        assert!(metrics.contains(
+            "rcli_http_requests_total{method=\"GET\",route=\"/{*path}\",status=\"200\"} 1"
+        ));
+        assert!(metrics.contains(&format!(
+            "rcli_http_response_bytes_total{{method=\"GET\",route=\"/{{*path}}\"}} {}",
+            len
+        )));
+        assert!(metrics.contains(
+            "rcli_http_requests_total{method=\"OTHER\",route=\"/_echo\",status=\"200\"} 2"
+        ));
+        assert!(!metrics.contains("BREW"));
+
+        let res = router
+            .oneshot(Request::get("/_info").body(Body::empty())?)
+            .await?;
+        let info: serde_json::Value =
+            serde_json::from_slice(&to_bytes(res.into_body(), usize::MAX).await?)?;
+        assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+        assert!(info["uptime_secs"].is_u64());
+
+        // 未启用的端点不会注册
+        let router = test_router(&["--dir", "fixtures", "--admin", "health"]);
+        assert_eq!(
+            get_status(&router, "/_metrics").await,
+            StatusCode::NOT_FOUND
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,372 @@
+use anyhow::{anyhow, Result};
+use axum::{
+    body::{Body, Bytes},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Datelike, Local, Timelike};
+use flate2::{write::GzEncoder, Compression};
+use futures::stream;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::SystemTime,
+};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use super::HttpServeState;
+
+// 发送给客户端的数据块大小
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// 目录打包下载的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ArchiveFormat {
+    Zip,
+    TarGz,
+}
+
+impl FromStr for ArchiveFormat {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "zip" => Ok(ArchiveFormat::Zip),
+            "tar.gz" | "tgz" => Ok(ArchiveFormat::TarGz),
+            _ => Err(anyhow!(
+                "Invalid archive format {:?}, expected zip or tar.gz",
+                s
+            )),
+        }
+    }
+}
+
+impl ArchiveFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::TarGz => "tar.gz",
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "application/zip",
+            ArchiveFormat::TarGz => "application/gzip",
+        }
+    }
+}
+
+// 归档中的一项，生成文件清单时同样使用
+#[derive(Debug)]
+pub(super) struct ArchiveEntry {
+    // 归档内的路径，以/分隔
+    pub(super) name: String,
+    // 磁盘上的路径
+    pub(super) path: PathBuf,
+    pub(super) is_dir: bool,
+    pub(super) size: u64,
+    pub(super) modified: Option<SystemTime>,
+}
+
+// 将目录打包为zip或tar.gz，边打包边发送，不在磁盘上生成临时文件
+pub(super) async fn serve_archive(
+    state: &HttpServeState,
+    dir: &Path,
+    format: ArchiveFormat,
+) -> Response {
+    let root = state.path.clone();
+    let follow_symlinks = state.follow_symlinks;
+    let base = dir.to_path_buf();
+    let entries =
+        tokio::task::spawn_blocking(move || collect_entries(&root, &base, follow_symlinks)).await;
+    let entries = match entries {
+        Ok(Ok(entries)) => entries,
+        Ok(Err(e)) => {
+            warn!("Error reading directory {:?}: {:?}", dir, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Error reading directory").into_response();
+        }
+        Err(e) => {
+            warn!("Error reading directory {:?}: {:?}", dir, e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let total: u64 = entries.iter().map(|e| e.size).sum();
+    if total > state.max_archive_size {
+        return (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!(
+                "Directory size {} exceeds the archive limit of {} bytes",
+                total, state.max_archive_size
+            ),
+        )
+            .into_response();
+    }
+    info!(
+        "Archiving {:?} as {} ({} entries, {} bytes)",
+        dir,
+        format.extension(),
+        entries.len(),
+        total
+    );
+
+    let (tx, rx) = mpsc::channel::<io::Result<Bytes>>(4);
+    tokio::task::spawn_blocking(move || {
+        let mut writer = ChannelWriter::new(tx.clone());
+        let result = match format {
+            ArchiveFormat::Zip => write_zip(&mut writer, &entries, total),
+            ArchiveFormat::TarGz => write_tar_gz(&mut writer, &entries),
+        }
+        .and_then(|_| writer.flush());
+        // 打包失败时让响应体以错误结束，客户端不会把不完整的归档当作成功下载
+        if let Err(e) = result {
+            warn!("Error writing archive: {:?}", e);
+            let _ = tx.blocking_send(Err(e));
+        }
+    });
+    let body = Body::from_stream(stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    }));
+
+    let name = dir
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "archive".to_string());
+    let filename = format!("{}.{}", name, format.extension());
+    let disposition = format!(
+        "attachment; filename*=UTF-8''{}",
+        utf8_percent_encode(&filename, NON_ALPHANUMERIC)
+    );
+    let mut res = Response::new(body);
+    let headers = res.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(format.content_type()),
+    );
+    if let Ok(value) = HeaderValue::from_str(&disposition) {
+        headers.insert(header::CONTENT_DISPOSITION, value);
+    }
+    res
+}
+
+// 递归收集目录中的文件与子目录
+// 未开启follow_symlinks时跳过指向服务目录之外的符号链接，并避免符号链接造成的循环
+pub(super) fn collect_entries(
+    root: &Path,
+    dir: &Path,
+    follow_symlinks: bool,
+) -> io::Result<Vec<ArchiveEntry>> {
+    let mut entries = Vec::new();
+    let mut visited = HashSet::new();
+    visited.insert(dir.canonicalize()?);
+    let mut stack = vec![(dir.to_path_buf(), String::new())];
+    while let Some((dir, prefix)) = stack.pop() {
+        let mut children: Vec<_> = std::fs::read_dir(&dir)?.collect::<io::Result<_>>()?;
+        children.sort_by_key(|e| e.file_name());
+        for child in children {
+            let name = child.file_name().to_string_lossy().into_owned();
+            // 跳过上传过程中的临时文件
+            if name.ends_with(".rcli-upload") {
+                continue;
+            }
+            let Ok(real) = child.path().canonicalize() else {
+                continue;
+            };
+            if !follow_symlinks && !real.starts_with(root) {
+                continue;
+            }
+            let meta = std::fs::metadata(&real)?;
+            let name = format!("{}{}", prefix, name);
+            if meta.is_dir() {
+                if !visited.insert(real.clone()) {
+                    continue;
+                }
+                stack.push((real.clone(), format!("{}/", name)));
+            }
+            entries.push(ArchiveEntry {
+                name,
+                path: real,
+                is_dir: meta.is_dir(),
+                size: if meta.is_dir() { 0 } else { meta.len() },
+                modified: meta.modified().ok(),
+            });
+        }
+    }
+    Ok(entries)
+}
+
+// 写入zip归档
+fn write_zip(writer: &mut ChannelWriter, entries: &[ArchiveEntry], total: u64) -> io::Result<()> {
+    let mut zip = ZipWriter::new_stream(writer);
+    for entry in entries {
+        let mut options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .large_file(total >= u32::MAX as u64);
+        if let Some(modified) = entry.modified.and_then(zip_datetime) {
+            options = options.last_modified_time(modified);
+        }
+        if entry.is_dir {
+            zip.add_directory(entry.name.as_str(), options)?;
+        } else {
+            zip.start_file(entry.name.as_str(), options)?;
+            io::copy(&mut File::open(&entry.path)?, &mut zip)?;
+        }
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+// 将修改时间转换为zip使用的本地时间
+fn zip_datetime(time: SystemTime) -> Option<zip::DateTime> {
+    let t: DateTime<Local> = time.into();
+    zip::DateTime::from_date_and_time(
+        u16::try_from(t.year()).ok()?,
+        t.month() as u8,
+        t.day() as u8,
+        t.hour() as u8,
+        t.minute() as u8,
+        t.second() as u8,
+    )
+    .ok()
+}
+
+// 写入tar.gz归档
+fn write_tar_gz(writer: &mut ChannelWriter, entries: &[ArchiveEntry]) -> io::Result<()> {
+    let gz = GzEncoder::new(writer, Compression::default());
+    let mut tar = tar::Builder::new(gz);
+    for entry in entries {
+        if entry.is_dir {
+            tar.append_dir(&entry.name, &entry.path)?;
+        } else {
+            tar.append_path_with_name(&entry.path, &entry.name)?;
+        }
+    }
+    tar.into_inner()?.finish()?;
+    Ok(())
+}
+
+// 将写入的数据按块发送到响应体的Write实现
+struct ChannelWriter {
+    tx: mpsc::Sender<io::Result<Bytes>>,
+    buf: Vec<u8>,
+}
+
+impl ChannelWriter {
+    fn new(tx: mpsc::Sender<io::Result<Bytes>>) -> Self {
+        Self {
+            tx,
+            buf: Vec::with_capacity(CHUNK_SIZE),
+        }
+    }
+
+    fn send(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let chunk = Bytes::from(std::mem::replace(
+            &mut self.buf,
+            Vec::with_capacity(CHUNK_SIZE),
+        ));
+        // 客户端断开后停止打包
+        self.tx
+            .blocking_send(Ok(chunk))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "Client disconnected"))
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        if self.buf.len() >= CHUNK_SIZE {
+            self.send()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::test_util::{get_status, test_router};
+    use axum::{
+        body::{to_bytes, Body},
+        http::Request,
+    };
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_collect_entries() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let root = tmp.path().canonicalize()?;
+        std::fs::create_dir(root.join("sub"))?;
+        std::fs::write(root.join("a.txt"), "hello")?;
+        std::fs::write(root.join("sub/b.txt"), "world!")?;
+        std::fs::write(root.join(".a.txt.0000.rcli-upload"), "partial")?;
+        let entries = collect_entries(&root, &root, false)?;
+        let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["a.txt", "sub", "sub/b.txt"]);
+        assert_eq!(entries.iter().map(|e| e.size).sum::<u64>(), 11);
+        assert_eq!("tgz".parse::<ArchiveFormat>()?, ArchiveFormat::TarGz);
+        assert!("rar".parse::<ArchiveFormat>().is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_archive_download() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        std::fs::create_dir(tmp.path().join("docs"))?;
+        std::fs::write(tmp.path().join("docs/a.txt"), "hello")?;
+        let dir = tmp.path().to_str().unwrap();
+        let router = test_router(&["--dir", dir, "--max-archive-size", "8"]);
+
+        let req = Request::get("/docs/?download=tar.gz")
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())?;
+        let res = router.clone().oneshot(req).await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "application/gzip");
+        assert!(!res.headers().contains_key(header::CONTENT_ENCODING));
+        assert_eq!(
+            res.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename*=UTF-8''docs%2Etar%2Egz"
+        );
+        let body = to_bytes(res.into_body(), usize::MAX).await?;
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(&body[..]));
+        let mut names = Vec::new();
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let mut content = String::new();
+            std::io::Read::read_to_string(&mut entry, &mut content)?;
+            names.push((entry.path()?.display().to_string(), content));
+        }
+        assert_eq!(names, vec![("a.txt".to_string(), "hello".to_string())]);
+
+        let res = router
+            .clone()
+            .oneshot(Request::get("/docs?download=zip").body(Body::empty())?)
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = to_bytes(res.into_body(), usize::MAX).await?;
+        assert!(body.starts_with(b"PK\x03\x04"));
+
+        assert_eq!(
+            get_status(&router, "/docs/?download=rar").await,
+            StatusCode::BAD_REQUEST
+        );
+        std::fs::write(tmp.path().join("docs/b.txt"), "too large")?;
+        assert_eq!(
+            get_status(&router, "/docs/?download=zip").await,
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+        Ok(())
+    }
+}
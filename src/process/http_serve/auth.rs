@@ -0,0 +1,240 @@
+use anyhow::{anyhow, Result};
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use tracing::warn;
+
+use super::HttpServeState;
+
+// 认证质询中使用的realm
+const REALM: &str = "rcli";
+
+// 访问服务所需的凭据：基本认证的用户名/密码与Bearer令牌
+#[derive(Debug, Default)]
+pub(super) struct Credentials {
+    // 用户名到密码的映射
+    users: HashMap<String, Secret>,
+    // Bearer令牌的哈希，比较哈希可以避免逐字节比较带来的时序差异
+    tokens: Vec<blake3::Hash>,
+    // 已经通过bcrypt校验的凭据哈希，避免每个请求都进行耗时的bcrypt计算
+    verified: Mutex<HashSet<blake3::Hash>>,
+}
+
+// 用户密码的存储形式
+#[derive(Debug)]
+enum Secret {
+    // 明文密码的blake3哈希
+    Plain(blake3::Hash),
+    // htpasswd -B生成的bcrypt哈希
+    Bcrypt(String),
+}
+
+impl Credentials {
+    // 加载user:pass凭据、凭据文件与Bearer令牌
+    pub(super) fn try_new(
+        users: &[String],
+        auth_file: Option<&Path>,
+        tokens: &[String],
+    ) -> Result<Self> {
+        let mut creds = Self::default();
+        for auth in users {
+            creds.add_user(auth)?;
+        }
+        if let Some(auth_file) = auth_file {
+            let content = std::fs::read_to_string(auth_file)?;
+            for line in content.lines().map(str::trim) {
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                creds.add_user(line)?;
+            }
+        }
+        creds.tokens = tokens
+            .iter()
+            .map(|token| blake3::hash(token.as_bytes()))
+            .collect();
+        Ok(creds)
+    }
+
+    // 添加一条user:pass格式的凭据，密码以$2开头时视为bcrypt哈希
+    fn add_user(&mut self, entry: &str) -> Result<()> {
+        let (user, pass) = entry
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Invalid credential {:?}, expected user:pass", entry))?;
+        let secret = if pass.starts_with("$2") {
+            Secret::Bcrypt(pass.to_string())
+        } else {
+            Secret::Plain(blake3::hash(pass.as_bytes()))
+        };
+        self.users.insert(user.to_string(), secret);
+        Ok(())
+    }
+
+    // 是否配置了任何凭据
+    pub(super) fn is_empty(&self) -> bool {
+        self.users.is_empty() && self.tokens.is_empty()
+    }
+
+    // 校验请求的Authorization头
+    pub(super) async fn authorize(&self, headers: &HeaderMap) -> bool {
+        let Some(value) = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+        else {
+            return false;
+        };
+        let Some((scheme, param)) = value.trim().split_once(' ') else {
+            return false;
+        };
+        if scheme.eq_ignore_ascii_case("basic") {
+            self.check_basic(param.trim()).await
+        } else if scheme.eq_ignore_ascii_case("bearer") {
+            let hash = blake3::hash(param.trim().as_bytes());
+            self.tokens.contains(&hash)
+        } else {
+            false
+        }
+    }
+
+    // 校验基本认证的用户名和密码
+    // bcrypt校验耗时较长，放到阻塞线程池中执行，避免错误密码的请求占满异步工作线程
+    async fn check_basic(&self, param: &str) -> bool {
+        let Ok(decoded) = STANDARD.decode(param) else {
+            return false;
+        };
+        let Ok(decoded) = String::from_utf8(decoded) else {
+            return false;
+        };
+        let Some((user, pass)) = decoded.split_once(':') else {
+            return false;
+        };
+        match self.users.get(user) {
+            Some(Secret::Plain(hash)) => blake3::hash(pass.as_bytes()) == *hash,
+            Some(Secret::Bcrypt(hash)) => {
+                let key = blake3::hash(decoded.as_bytes());
+                if self.verified.lock().is_ok_and(|v| v.contains(&key)) {
+                    return true;
+                }
+                let (pass, hash) = (pass.to_string(), hash.clone());
+                let ok = tokio::task::spawn_blocking(move || bcrypt::verify(pass, &hash))
+                    .await
+                    .is_ok_and(|res| res.unwrap_or(false));
+                if ok {
+                    if let Ok(mut verified) = self.verified.lock() {
+                        verified.insert(key);
+                    }
+                }
+                ok
+            }
+            None => false,
+        }
+    }
+
+    // 生成401响应，附带所有已启用认证方式的WWW-Authenticate质询
+    pub(super) fn challenge(&self) -> Response {
+        let mut res = (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+        let mut challenges = Vec::new();
+        if !self.users.is_empty() {
+            challenges.push(format!("Basic realm=\"{}\", charset=\"UTF-8\"", REALM));
+        }
+        if !self.tokens.is_empty() {
+            challenges.push(format!("Bearer realm=\"{}\"", REALM));
+        }
+        for challenge in challenges {
+            if let Ok(value) = HeaderValue::from_str(&challenge) {
+                res.headers_mut().append(header::WWW_AUTHENTICATE, value);
+            }
+        }
+        res
+    }
+}
+
+// 认证中间件，未通过认证的请求返回401
+pub(super) async fn require_auth(
+    State(state): State<Arc<HttpServeState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if state.credentials.authorize(req.headers()).await {
+        next.run(req).await
+    } else {
+        warn!("Unauthorized request for {}", req.uri());
+        state.credentials.challenge()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::test_util::test_router;
+    use axum::{body::Body, http::Request};
+    use tower::ServiceExt;
+
+    fn basic(user_pass: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let value = format!("Basic {}", STANDARD.encode(user_pass));
+        headers.insert(header::AUTHORIZATION, value.parse().unwrap());
+        headers
+    }
+
+    #[tokio::test]
+    async fn test_basic_auth() -> Result<()> {
+        let mut creds = Credentials::default();
+        creds.add_user("alice:secret")?;
+        creds.add_user(&format!("bob:{}", bcrypt::hash("hunter2", 4)?))?;
+        assert!(creds.authorize(&basic("alice:secret")).await);
+        assert!(!creds.authorize(&basic("alice:wrong")).await);
+        assert!(creds.authorize(&basic("bob:hunter2")).await);
+        // 第二次命中缓存
+        assert!(creds.authorize(&basic("bob:hunter2")).await);
+        assert!(!creds.authorize(&basic("bob:wrong")).await);
+        assert!(!creds.authorize(&basic("carol:secret")).await);
+        assert!(creds.add_user("invalid").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_bearer_auth() {
+        let creds = Credentials {
+            tokens: vec![blake3::hash(b"t0ken")],
+            ..Default::default()
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, "Bearer t0ken".parse().unwrap());
+        assert!(creds.authorize(&headers).await);
+        headers.insert(header::AUTHORIZATION, "Bearer other".parse().unwrap());
+        assert!(!creds.authorize(&headers).await);
+        assert_eq!(
+            creds.challenge().headers()[header::WWW_AUTHENTICATE],
+            "Bearer realm=\"rcli\""
+        );
+    }
+
+    #[tokio::test]
+    async fn test_auth_required() -> Result<()> {
+        let router = test_router(&["--dir", "fixtures", "--auth", "alice:secret"]);
+        let res = router
+            .clone()
+            .oneshot(Request::get("/b64.txt").body(Body::empty())?)
+            .await?;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert!(res.headers().contains_key(header::WWW_AUTHENTICATE));
+
+        // alice:secret
+        let req = Request::get("/b64.txt")
+            .header(header::AUTHORIZATION, "Basic YWxpY2U6c2VjcmV0")
+            .body(Body::empty())?;
+        let res = router.oneshot(req).await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        Ok(())
+    }
+}
@@ -0,0 +1,278 @@
+use axum::http::{header, HeaderMap};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+use tracing::warn;
+
+use super::range::truncate_to_secs;
+
+// 不超过该大小的文件在请求中直接计算哈希，更大的文件在后台计算
+const INLINE_HASH_SIZE: u64 = 8 * 1024 * 1024;
+
+// 文件内容的blake3哈希缓存，文件的修改时间或长度变化后自动失效
+#[derive(Debug, Default)]
+pub(super) struct HashCache {
+    entries: Arc<Mutex<HashMap<PathBuf, CachedHash>>>,
+    // 正在后台计算哈希的文件
+    pending: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+// 缓存的一条哈希记录
+#[derive(Debug, Clone, Copy)]
+struct CachedHash {
+    // 计算哈希时文件的修改时间
+    modified: Option<SystemTime>,
+    // 计算哈希时文件的长度
+    len: u64,
+    // 文件内容的blake3哈希
+    hash: blake3::Hash,
+}
+
+impl HashCache {
+    // 获取文件的哈希，缓存失效时在阻塞线程池中重新计算
+    pub(super) async fn get_or_compute(
+        &self,
+        p: &Path,
+        len: u64,
+        modified: Option<SystemTime>,
+    ) -> std::io::Result<blake3::Hash> {
+        if let Some(hash) = self.get(p, len, modified) {
+            return Ok(hash);
+        }
+        let path = p.to_path_buf();
+        let hash = tokio::task::spawn_blocking(move || hash_file(&path)).await??;
+        insert(&self.entries, p.to_path_buf(), len, modified, hash);
+        Ok(hash)
+    }
+
+    // 获取文件的哈希，大文件的缓存失效时在后台计算并返回None，避免首次请求等待整个文件读完
+    pub(super) async fn get_or_spawn(
+        &self,
+        p: &Path,
+        len: u64,
+        modified: Option<SystemTime>,
+    ) -> std::io::Result<Option<blake3::Hash>> {
+        if len <= INLINE_HASH_SIZE {
+            return self.get_or_compute(p, len, modified).await.map(Some);
+        }
+        if let Some(hash) = self.get(p, len, modified) {
+            return Ok(Some(hash));
+        }
+        let path = p.to_path_buf();
+        let started = self
+            .pending
+            .lock()
+            .is_ok_and(|mut pending| pending.insert(path.clone()));
+        if started {
+            let entries = self.entries.clone();
+            let pending = self.pending.clone();
+            tokio::task::spawn_blocking(move || {
+                match hash_file(&path) {
+                    Ok(hash) => insert(&entries, path.clone(), len, modified, hash),
+                    Err(e) => warn!("Error hashing {:?}: {:?}", path, e),
+                }
+                if let Ok(mut pending) = pending.lock() {
+                    pending.remove(&path);
+                }
+            });
+        }
+        Ok(None)
+    }
+
+    // 查找与文件当前长度和修改时间一致的哈希
+    fn get(&self, p: &Path, len: u64, modified: Option<SystemTime>) -> Option<blake3::Hash> {
+        self.lookup(p)
+            .filter(|cached| cached.len == len && cached.modified == modified)
+            .map(|cached| cached.hash)
+    }
+
+    fn lookup(&self, p: &Path) -> Option<CachedHash> {
+        self.entries.lock().ok()?.get(p).copied()
+    }
+}
+
+// 计算文件内容的blake3哈希
+fn hash_file(p: &Path) -> std::io::Result<blake3::Hash> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(std::fs::File::open(p)?)?;
+    Ok(hasher.finalize())
+}
+
+fn insert(
+    entries: &Mutex<HashMap<PathBuf, CachedHash>>,
+    p: PathBuf,
+    len: u64,
+    modified: Option<SystemTime>,
+    hash: blake3::Hash,
+) {
+    if let Ok(mut entries) = entries.lock() {
+        entries.insert(
+            p,
+            CachedHash {
+                modified,
+                len,
+                hash,
+            },
+        );
+    }
+}
+
+// 由文件哈希生成强ETag
+pub(super) fn etag(hash: &blake3::Hash) -> String {
+    format!("\"{}\"", hash.to_hex())
+}
+
+// 哈希尚未计算出来时，由文件长度与修改时间生成弱ETag
+pub(super) fn weak_etag(len: u64, modified: Option<SystemTime>) -> String {
+    let mtime = modified
+        .and_then(|m| m.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_nanos());
+    format!("W/\"{:x}-{:x}\"", len, mtime)
+}
+
+// 由文件哈希生成RFC 9530的Repr-Digest头，值为base64编码的字节序列
+pub(super) fn repr_digest(hash: &blake3::Hash) -> String {
+    format!("blake3=:{}:", STANDARD.encode(hash.as_bytes()))
+}
+
+// 由文件哈希生成RFC 3230的Digest头，供只支持旧规范的客户端使用
+pub(super) fn digest(hash: &blake3::Hash) -> String {
+    format!("blake3={}", STANDARD.encode(hash.as_bytes()))
+}
+
+// 按照RFC 9110的优先级判断条件请求是否可以返回304：
+// 存在If-None-Match时只比较ETag，否则比较If-Modified-Since
+pub(super) fn is_not_modified(
+    headers: &HeaderMap,
+    etag: &str,
+    modified: Option<SystemTime>,
+) -> bool {
+    if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH) {
+        let Ok(if_none_match) = if_none_match.to_str() else {
+            return false;
+        };
+        return if_none_match.split(',').map(str::trim).any(|tag| {
+            // If-None-Match使用弱比较，忽略W/前缀
+            tag == "*" || tag.trim_start_matches("W/") == etag.trim_start_matches("W/")
+        });
+    }
+    let since = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| httpdate::parse_http_date(v).ok());
+    match (since, modified) {
+        (Some(since), Some(modified)) => truncate_to_secs(modified) <= since,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::test_util::test_router;
+    use anyhow::Result;
+    use axum::{
+        body::Body,
+        http::{HeaderValue, Request, StatusCode},
+    };
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_is_not_modified() {
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut headers = HeaderMap::new();
+        assert!(!is_not_modified(&headers, "\"abc\"", Some(modified)));
+
+        headers.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_str(&httpdate::fmt_http_date(modified)).unwrap(),
+        );
+        assert!(is_not_modified(&headers, "\"abc\"", Some(modified)));
+        let changed = modified + Duration::from_secs(1);
+        assert!(!is_not_modified(&headers, "\"abc\"", Some(changed)));
+
+        // If-None-Match优先于If-Modified-Since
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"xyz\""));
+        assert!(!is_not_modified(&headers, "\"abc\"", Some(modified)));
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_static("\"xyz\", W/\"abc\""),
+        );
+        assert!(is_not_modified(&headers, "\"abc\"", Some(changed)));
+    }
+
+    #[tokio::test]
+    async fn test_hash_cache() -> anyhow::Result<()> {
+        let cache = HashCache::default();
+        let p = Path::new("fixtures/b64.txt");
+        let meta = std::fs::metadata(p)?;
+        let hash = cache
+            .get_or_compute(p, meta.len(), meta.modified().ok())
+            .await?;
+        assert_eq!(hash, blake3::hash(&std::fs::read(p)?));
+        assert!(cache.lookup(p).is_some());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_hash_large_file_in_background() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let p = tmp.path().join("large.bin");
+        let content = vec![7u8; INLINE_HASH_SIZE as usize + 1];
+        std::fs::write(&p, &content)?;
+        let meta = std::fs::metadata(&p)?;
+        let cache = HashCache::default();
+        // 首次请求不等待哈希计算
+        let hash = cache
+            .get_or_spawn(&p, meta.len(), meta.modified().ok())
+            .await?;
+        assert!(hash.is_none());
+        let mut hash = None;
+        for _ in 0..100 {
+            hash = cache
+                .get_or_spawn(&p, meta.len(), meta.modified().ok())
+                .await?;
+            if hash.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(hash, Some(blake3::hash(&content)));
+
+        let etag = weak_etag(meta.len(), meta.modified().ok());
+        assert!(etag.starts_with("W/\""));
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_str(&etag)?);
+        assert!(is_not_modified(&headers, &etag, None));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_conditional_get() -> Result<()> {
+        let router = test_router(&["--dir", "fixtures", "--cache-control", "no-cache"]);
+        let req = Request::get("/b64.txt").body(Body::empty())?;
+        let res = router.clone().oneshot(req).await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CACHE_CONTROL], "no-cache");
+        let etag = res.headers()[header::ETAG].clone();
+        let last_modified = res.headers()[header::LAST_MODIFIED].clone();
+
+        let req = Request::get("/b64.txt")
+            .header(header::IF_NONE_MATCH, etag)
+            .body(Body::empty())?;
+        let res = router.clone().oneshot(req).await?;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        let req = Request::get("/b64.txt")
+            .header(header::IF_MODIFIED_SINCE, last_modified)
+            .body(Body::empty())?;
+        let res = router.oneshot(req).await?;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        Ok(())
+    }
+}
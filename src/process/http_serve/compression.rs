@@ -0,0 +1,204 @@
+use axum::{
+    extract::Request,
+    http::{header, Extensions, HeaderMap, HeaderValue, StatusCode, Version},
+    middleware::Next,
+    response::Response,
+};
+use std::path::{Path, PathBuf};
+use tower_http::compression::{
+    predicate::{NotForContentType, Predicate, SizeAbove},
+    CompressionLayer,
+};
+
+use super::{file::REPR_DIGEST, HttpServeState};
+use crate::CompressionAlgorithm;
+
+// 预压缩文件的扩展名与对应的Content-Encoding，按优先级排列
+const PRECOMPRESSED: [(&str, &str); 3] = [("br", "br"), ("zst", "zstd"), ("gz", "gzip")];
+
+// 根据启用的算法和最小大小创建实时压缩层
+// 图片、gRPC、SSE与已经压缩过的归档响应不压缩，已经带有Content-Encoding或Content-Range的响应也会被跳过
+// 带有Repr-Digest的文件响应也不压缩，否则摘要与实际发送的内容不一致
+pub(super) fn compression_layer(
+    algorithms: &[CompressionAlgorithm],
+    min_size: u16,
+) -> CompressionLayer<impl Predicate> {
+    let enabled = |algorithm| algorithms.contains(&algorithm);
+    CompressionLayer::new()
+        .gzip(enabled(CompressionAlgorithm::Gzip))
+        .br(enabled(CompressionAlgorithm::Br))
+        .deflate(enabled(CompressionAlgorithm::Deflate))
+        .zstd(enabled(CompressionAlgorithm::Zstd))
+        .compress_when(
+            SizeAbove::new(min_size)
+                .and(NotForContentType::GRPC)
+                .and(NotForContentType::IMAGES)
+                .and(NotForContentType::SSE)
+                .and(NotForContentType::const_new("application/zip"))
+                .and(NotForContentType::const_new("application/gzip"))
+                .and(
+                    |_: StatusCode, _: Version, headers: &HeaderMap, _: &Extensions| {
+                        !headers.contains_key(REPR_DIGEST)
+                    },
+                ),
+        )
+}
+
+// 实时压缩后的内容与原始文件不再逐字节相同，将原始文件的强ETag改为弱ETag
+// 预压缩文件的ETag已带有编码后缀（见encoded_etag），保持不变
+pub(super) async fn weaken_etag(req: Request, next: Next) -> Response {
+    let mut res = next.run(req).await;
+    let Some(encoding) = res
+        .headers()
+        .get(header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| *v != "identity")
+    else {
+        return res;
+    };
+    let suffix = format!("-{}\"", encoding);
+    let weak = res
+        .headers()
+        .get(header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .filter(|etag| etag.starts_with('"') && !etag.ends_with(&suffix))
+        .and_then(|etag| HeaderValue::from_str(&format!("W/{}", etag)).ok());
+    if let Some(weak) = weak {
+        res.headers_mut().insert(header::ETAG, weak);
+    }
+    res
+}
+
+// 为预压缩文件的ETag加上编码后缀，与原始文件的ETag区分
+pub(super) fn encoded_etag(etag: &str, encoding: &str) -> String {
+    match etag.strip_suffix('"') {
+        Some(tag) => format!("{}-{}\"", tag, encoding),
+        None => etag.to_string(),
+    }
+}
+
+// 查找客户端可以接受的预压缩同名文件，返回文件路径和对应的Content-Encoding
+pub(super) fn find_precompressed(
+    state: &HttpServeState,
+    p: &Path,
+    headers: &HeaderMap,
+) -> Option<(PathBuf, &'static str)> {
+    if !state.precompressed {
+        return None;
+    }
+    let accepted = accepted_encodings(headers);
+    let name = p.file_name()?.to_str()?;
+    PRECOMPRESSED
+        .iter()
+        .filter(|(_, encoding)| accepted.contains(encoding))
+        .map(|(ext, encoding)| (p.with_file_name(format!("{}.{}", name, ext)), *encoding))
+        .find(|(sibling, _)| {
+            // 预压缩文件同样不能通过符号链接逃出服务目录
+            match sibling.canonicalize() {
+                Ok(real) => {
+                    real.is_file() && (state.follow_symlinks || real.starts_with(&state.path))
+                }
+                Err(_) => false,
+            }
+        })
+}
+
+// 解析Accept-Encoding请求头，返回q值大于0的编码
+fn accepted_encodings(headers: &HeaderMap) -> Vec<&str> {
+    let Some(value) = headers
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+    else {
+        return Vec::new();
+    };
+    value
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';').map(str::trim);
+            let encoding = parts.next()?;
+            let rejected = parts.any(|param| {
+                param
+                    .strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .is_some_and(|q| q <= 0.0)
+            });
+            (!encoding.is_empty() && !rejected).then_some(encoding)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::test_util::test_router;
+    use anyhow::Result;
+    use axum::{
+        body::{to_bytes, Body},
+        http::Request,
+    };
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_accepted_encodings() {
+        let mut headers = HeaderMap::new();
+        assert!(accepted_encodings(&headers).is_empty());
+        headers.insert(
+            header::ACCEPT_ENCODING,
+            HeaderValue::from_static("gzip, br;q=0, zstd;q=0.5"),
+        );
+        assert_eq!(accepted_encodings(&headers), vec!["gzip", "zstd"]);
+    }
+
+    #[tokio::test]
+    async fn test_compression() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        std::fs::write(tmp.path().join("a.txt"), "a".repeat(4096))?;
+        std::fs::write(tmp.path().join("b.js"), "b".repeat(4096))?;
+        std::fs::write(tmp.path().join("b.js.br"), "brotli bytes")?;
+        let root = tmp.path().to_str().unwrap();
+        let router = test_router(&["--dir", root, "--compress", "gzip"]);
+        let get = |uri: &str, encoding: &'static str| {
+            let req = Request::get(uri)
+                .header(header::ACCEPT_ENCODING, encoding)
+                .body(Body::empty())
+                .unwrap();
+            router.clone().oneshot(req)
+        };
+
+        let res = get("/a.txt", "gzip, br").await?;
+        assert_eq!(res.headers()[header::CONTENT_ENCODING], "gzip");
+        // 实时压缩的响应使用弱ETag，仍然支持条件请求
+        let etag = res.headers()[header::ETAG].clone();
+        assert!(etag.to_str()?.starts_with("W/\""));
+        let req = Request::get("/a.txt")
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .header(header::IF_NONE_MATCH, etag.clone())
+            .body(Body::empty())?;
+        let res = router.clone().oneshot(req).await?;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        let res = get("/a.txt", "br").await?;
+        assert!(!res.headers().contains_key(header::CONTENT_ENCODING));
+        assert_eq!(
+            format!("W/{}", res.headers()[header::ETAG].to_str()?),
+            etag.to_str()?
+        );
+
+        let res = get("/b.js", "gzip, br").await?;
+        assert_eq!(res.headers()[header::CONTENT_ENCODING], "br");
+        assert!(res.headers()[header::ETAG].to_str()?.ends_with("-br\""));
+        assert_eq!(
+            res.headers()[header::CONTENT_TYPE],
+            "text/javascript; charset=utf-8"
+        );
+        let body = to_bytes(res.into_body(), usize::MAX).await?;
+        assert_eq!(&body[..], b"brotli bytes");
+
+        let router = test_router(&["--dir", root, "--no-compress", "--no-precompressed"]);
+        let req = Request::get("/b.js")
+            .header(header::ACCEPT_ENCODING, "gzip, br")
+            .body(Body::empty())?;
+        let res = router.oneshot(req).await?;
+        assert!(!res.headers().contains_key(header::CONTENT_ENCODING));
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+// rcli-serve.toml配置文件，每个[[mount]]描述一个挂载点
+//
+// [[mount]]
+// path = "/docs"
+// dir = "./target/doc"
+// cache_control = "max-age=3600"
+// listing = false
+// auth = ["alice:secret"]
+// [mount.headers]
+// X-Frame-Options = "DENY"
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(super) struct ServeConfig {
+    #[serde(default, rename = "mount")]
+    pub(super) mounts: Vec<MountConfig>,
+}
+
+// 单个挂载点的配置，未设置的选项沿用命令行参数
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(super) struct MountConfig {
+    // 路径前缀，"/"表示根目录
+    pub(super) path: String,
+    // 挂载的目录，相对路径以配置文件所在目录为基准
+    pub(super) dir: PathBuf,
+    // user:pass格式的凭据；auth、auth_file、tokens任一项出现时替换命令行的认证设置
+    pub(super) auth: Option<Vec<String>>,
+    // 每行一条user:pass凭据的文件
+    pub(super) auth_file: Option<PathBuf>,
+    // Bearer令牌
+    pub(super) tokens: Option<Vec<String>>,
+    // 附加到响应的头部，与命令行的--header合并，同名时以这里为准
+    #[serde(default)]
+    pub(super) headers: BTreeMap<String, String>,
+    // 文件响应附带的Cache-Control头
+    pub(super) cache_control: Option<String>,
+    // 是否返回目录列表
+    pub(super) listing: Option<bool>,
+    // 是否允许上传文件
+    pub(super) upload: Option<bool>,
+    // 目录索引文件名
+    pub(super) index: Option<String>,
+    // 是否允许跟随指向目录之外的符号链接
+    pub(super) follow_symlinks: Option<bool>,
+    // 是否以WebDAV方式提供读写访问
+    pub(super) webdav: Option<bool>,
+}
+
+impl ServeConfig {
+    // 读取并解析配置文件，将其中的相对路径转换为相对配置文件所在目录的路径
+    pub(super) fn load(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let mut config: Self = toml::from_str(&content)?;
+        let base = Path::new(path).parent().unwrap_or(Path::new(""));
+        for mount in &mut config.mounts {
+            mount.dir = base.join(&mount.dir);
+            mount.auth_file = mount.auth_file.as_ref().map(|file| base.join(file));
+        }
+        Ok(config)
+    }
+}
+
+impl MountConfig {
+    // 是否单独配置了认证
+    pub(super) fn has_auth(&self) -> bool {
+        self.auth.is_some() || self.auth_file.is_some() || self.tokens.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::test_util::{get_status, test_router};
+    use axum::{
+        body::{to_bytes, Body},
+        http::{header, Request, StatusCode},
+    };
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_load_config() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let path = tmp.path().join("rcli-serve.toml");
+        std::fs::write(
+            &path,
+            r#"
+[[mount]]
+path = "/docs"
+dir = "docs"
+listing = false
+auth = []
+
+[mount.headers]
+X-Frame-Options = "DENY"
+"#,
+        )?;
+        let config = ServeConfig::load(path.to_str().unwrap())?;
+        assert_eq!(config.mounts.len(), 1);
+        let mount = &config.mounts[0];
+        assert_eq!(mount.dir, tmp.path().join("docs"));
+        assert_eq!(mount.listing, Some(false));
+        assert!(mount.has_auth());
+        assert_eq!(mount.headers["X-Frame-Options"], "DENY");
+
+        std::fs::write(
+            &path,
+            "[[mount]]\npath = \"/\"\ndir = \".\"\nlisting = \"yes\"\n",
+        )?;
+        assert!(ServeConfig::load(path.to_str().unwrap()).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mounts_and_config() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let config = tmp.path().join("rcli-serve.toml");
+        let fixtures = std::fs::canonicalize("fixtures")?;
+        std::fs::write(
+            &config,
+            format!(
+                "[[mount]]\npath = \"/private\"\ndir = {:?}\nlisting = false\n\
+                 auth = [\"alice:secret\"]\n[mount.headers]\nX-Test = \"1\"\n",
+                fixtures
+            ),
+        )?;
+        let router = test_router(&[
+            "--dir",
+            "fixtures",
+            "--mount",
+            "/static=src",
+            "--config",
+            config.to_str().unwrap(),
+        ]);
+        assert_eq!(get_status(&router, "/static/main.rs").await, StatusCode::OK);
+        let res = router
+            .clone()
+            .oneshot(Request::get("/static").body(Body::empty())?)
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = to_bytes(res.into_body(), usize::MAX).await?;
+        assert!(String::from_utf8_lossy(&body).contains("href=\"/static/main.rs\""));
+
+        // 挂载点单独的认证、响应头与目录列表设置
+        assert_eq!(
+            get_status(&router, "/private/b64.txt").await,
+            StatusCode::UNAUTHORIZED
+        );
+        let authorized = |uri: &str| {
+            Request::get(uri)
+                .header(header::AUTHORIZATION, "Basic YWxpY2U6c2VjcmV0")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let res = router
+            .clone()
+            .oneshot(authorized("/private/b64.txt"))
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()["x-test"], "1");
+        let res = router.clone().oneshot(authorized("/private/")).await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        // 根目录不受影响
+        let res = router
+            .oneshot(Request::get("/b64.txt").body(Body::empty())?)
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key("x-test"));
+        Ok(())
+    }
+}
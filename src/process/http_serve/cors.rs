@@ -0,0 +1,40 @@
+use anyhow::Result;
+use axum::http::{HeaderValue, Method};
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+// 根据允许的来源和方法创建CORS层，未指定来源时允许任意来源
+pub(super) fn cors_layer(origins: &[String], methods: &[String]) -> Result<CorsLayer> {
+    let allow_origin = if origins.is_empty() || origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        let origins = origins
+            .iter()
+            .map(|o| HeaderValue::from_str(o))
+            .collect::<Result<Vec<_>, _>>()?;
+        AllowOrigin::list(origins)
+    };
+    let methods = methods
+        .iter()
+        .map(|m| m.to_ascii_uppercase().parse::<Method>())
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(methods)
+        .allow_headers(Any)
+        .max_age(Duration::from_secs(3600)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cors_layer() {
+        let methods = vec!["get".to_string(), "PUT".to_string()];
+        assert!(cors_layer(&[], &methods).is_ok());
+        assert!(cors_layer(&["https://example.com".to_string()], &methods).is_ok());
+        assert!(cors_layer(&["bad\norigin".to_string()], &methods).is_err());
+        assert!(cors_layer(&[], &["NOT A METHOD".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,343 @@
+use anyhow::{anyhow, bail, Context, Result};
+use axum::{
+    body::{Body, Bytes},
+    http::{StatusCode, Uri},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use futures::{stream, StreamExt, TryStreamExt};
+use std::path::{Component, Path, PathBuf};
+use tokio::{fs, io::AsyncWriteExt};
+use tracing::{info, warn};
+
+use super::{
+    listing::encode_url_path,
+    manifest::{Manifest, ManifestEntry, MANIFEST_PATH, SIGNATURE_PATH},
+};
+use crate::process::{
+    http_request::{client, HttpClient},
+    text::{Ed25519Verifier, KeyLoader, TextVerify},
+};
+
+// 同时下载的文件数
+const CONCURRENCY: usize = 4;
+
+// 下载结果统计
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FetchSummary {
+    // 新下载的文件数
+    pub downloaded: usize,
+    // 本地已存在且哈希一致而跳过的文件数
+    pub unchanged: usize,
+}
+
+// 单个文件的处理结果
+enum Fetched {
+    Downloaded,
+    Unchanged,
+}
+
+// 按照http serve发布的清单下载整个目录，每个文件下载后都与清单中的blake3哈希比对
+// 支持http与https，https使用内置的webpki根证书
+// 指定公钥时先校验清单的Ed25519签名，因此不依赖HTTPS也能确认内容未被篡改
+pub async fn process_http_fetch(
+    url: &str,
+    output: &Path,
+    verify: Option<&str>,
+) -> Result<FetchSummary> {
+    let base = format!("{}/", url.trim_end_matches('/'));
+    let uri: Uri = base.parse()?;
+    if !matches!(uri.scheme_str(), Some("http" | "https")) {
+        bail!(
+            "Only http:// and https:// URLs are supported, got {:?}",
+            url
+        );
+    }
+    let client = client()?;
+
+    let manifest_bytes = get(&client, &format!("{}{}", base, MANIFEST_PATH)).await?;
+    if let Some(key) = verify {
+        let verifier = Ed25519Verifier::load(key)?;
+        let signature = get(&client, &format!("{}{}", base, SIGNATURE_PATH)).await?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature.trim_ascii())
+            .context("Invalid manifest signature")?;
+        let verified = verifier
+            .verify(manifest_bytes.as_ref(), &signature)
+            .unwrap_or(false);
+        if !verified {
+            bail!("Manifest signature verification failed");
+        }
+        info!("Manifest signature verified");
+    }
+    let manifest: Manifest = serde_json::from_slice(&manifest_bytes)?;
+    if manifest.algorithm != "blake3" {
+        bail!(
+            "Unsupported manifest hash algorithm {:?}",
+            manifest.algorithm
+        );
+    }
+
+    fs::create_dir_all(output).await?;
+    let results: Vec<_> = stream::iter(manifest.files)
+        .map(|entry| async {
+            let result = fetch_file(&client, &base, output, &entry).await;
+            (entry.path, result)
+        })
+        .buffer_unordered(CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut summary = FetchSummary::default();
+    let mut failed = Vec::new();
+    for (path, result) in results {
+        match result {
+            Ok(Fetched::Downloaded) => summary.downloaded += 1,
+            Ok(Fetched::Unchanged) => summary.unchanged += 1,
+            Err(e) => {
+                warn!("Rejected {}: {}", path, e);
+                failed.push(format!("{}: {}", path, e));
+            }
+        }
+    }
+    if !failed.is_empty() {
+        failed.sort();
+        bail!(
+            "{} file(s) failed verification:\n{}",
+            failed.len(),
+            failed.join("\n")
+        );
+    }
+    Ok(summary)
+}
+
+// 下载单个文件到临时文件，大小与哈希都与清单一致后才替换目标文件
+async fn fetch_file(
+    client: &HttpClient,
+    base: &str,
+    output: &Path,
+    entry: &ManifestEntry,
+) -> Result<Fetched> {
+    let dest = output.join(safe_relative(&entry.path)?);
+    if fs::metadata(&dest)
+        .await
+        .is_ok_and(|m| m.is_file() && m.len() == entry.size)
+        && hash_file(&dest).await? == entry.hash
+    {
+        return Ok(Fetched::Unchanged);
+    }
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    // raw=1跳过Markdown渲染、预览页面与实时刷新脚本注入等转换，取得与清单一致的原始内容
+    let url = format!("{}{}?raw=1", base, encode_url_path(&entry.path));
+    let res = client.get(url.parse()?).await?;
+    if res.status() != StatusCode::OK {
+        bail!("unexpected status {}", res.status());
+    }
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".rcli-fetch");
+    let tmp = dest.with_file_name(name);
+    let result = write_verified(&tmp, Body::new(res.into_body()), entry).await;
+    match result {
+        Ok(()) => {
+            fs::rename(&tmp, &dest).await?;
+            info!("Downloaded {}", entry.path);
+            Ok(Fetched::Downloaded)
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&tmp).await;
+            Err(e)
+        }
+    }
+}
+
+// 边写入边计算哈希，超出清单中的大小时立即停止
+async fn write_verified(tmp: &Path, body: Body, entry: &ManifestEntry) -> Result<()> {
+    let mut file = fs::File::create(tmp).await?;
+    let mut hasher = blake3::Hasher::new();
+    let mut written = 0u64;
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.try_next().await? {
+        written += chunk.len() as u64;
+        if written > entry.size {
+            bail!("size exceeds {} bytes", entry.size);
+        }
+        hasher.update(&chunk);
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+    if written != entry.size {
+        bail!("size mismatch: expected {}, got {}", entry.size, written);
+    }
+    let hash = hasher.finalize().to_hex();
+    if hash.as_str() != entry.hash {
+        bail!("hash mismatch: expected {}, got {}", entry.hash, hash);
+    }
+    Ok(())
+}
+
+// 请求清单或签名，非200时返回错误
+async fn get(client: &HttpClient, url: &str) -> Result<Bytes> {
+    let res = client
+        .get(url.parse()?)
+        .await
+        .with_context(|| format!("Failed to fetch {}", url))?;
+    if res.status() != StatusCode::OK {
+        bail!("Failed to fetch {}: {}", url, res.status());
+    }
+    let body = axum::body::to_bytes(Body::new(res.into_body()), usize::MAX).await?;
+    Ok(body)
+}
+
+// 计算本地文件的blake3哈希
+async fn hash_file(p: &Path) -> Result<String> {
+    let path = p.to_path_buf();
+    let hash = tokio::task::spawn_blocking(move || {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update_reader(std::fs::File::open(path)?)?;
+        Ok::<_, std::io::Error>(hasher.finalize())
+    })
+    .await??;
+    Ok(hash.to_hex().to_string())
+}
+
+// 清单中的路径只能包含普通路径段，防止写到输出目录之外
+fn safe_relative(path: &str) -> Result<PathBuf> {
+    let relative = Path::new(path);
+    let safe = !path.is_empty()
+        && relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)));
+    if safe {
+        Ok(relative.to_path_buf())
+    } else {
+        Err(anyhow!("unsafe path in manifest"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        process::text::{Ed25519Signer, KeyGenerator},
+        HttpServeOpts,
+    };
+    use axum::{routing::get as get_route, Router};
+    use clap::Parser;
+    use std::{net::SocketAddr, sync::Arc};
+
+    // 在随机端口上启动服务，返回服务地址
+    async fn spawn(router: Router) -> Result<String> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let service = router.into_make_service_with_connect_info::<SocketAddr>();
+        tokio::spawn(async move { axum::serve(listener, service).await });
+        Ok(format!("http://{}", addr))
+    }
+
+    #[tokio::test]
+    async fn test_fetch_signed_manifest() -> Result<()> {
+        let src = tempfile::tempdir()?;
+        std::fs::create_dir(src.path().join("sub dir"))?;
+        std::fs::write(src.path().join("a.txt"), "hello")?;
+        std::fs::write(src.path().join("sub dir/b.txt"), "world")?;
+        let opts = HttpServeOpts::parse_from([
+            "serve",
+            "--dir",
+            src.path().to_str().unwrap(),
+            "--sign-manifest",
+            "fixtures/ed25519.sk",
+        ]);
+        let state = super::super::ServerState::try_new(&opts)?;
+        let url = spawn(super::super::build_router(Arc::new(state))?).await?;
+
+        let out = tempfile::tempdir()?;
+        let summary = process_http_fetch(&url, out.path(), Some("fixtures/ed25519.pk")).await?;
+        assert_eq!(summary.downloaded, 2);
+        assert_eq!(
+            std::fs::read_to_string(out.path().join("sub dir/b.txt"))?,
+            "world"
+        );
+        let summary = process_http_fetch(&url, out.path(), Some("fixtures/ed25519.pk")).await?;
+        assert_eq!(summary.unchanged, 2);
+
+        // 用另一把公钥校验时拒绝整个清单
+        let keys = Ed25519Signer::generate()?;
+        let other = out.path().join("other.pk");
+        std::fs::write(&other, &keys[1])?;
+        let err = process_http_fetch(&url, out.path(), other.to_str()).await;
+        assert!(err.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fetch_skips_transforms() -> Result<()> {
+        let src = tempfile::tempdir()?;
+        std::fs::write(src.path().join("README.md"), "# Title\n")?;
+        std::fs::write(src.path().join("index.html"), "<body>hi</body>")?;
+        std::fs::write(src.path().join("data.csv"), "a,b\n1,2\n")?;
+        let opts = HttpServeOpts::parse_from([
+            "serve",
+            "--dir",
+            src.path().to_str().unwrap(),
+            "--integrity",
+            "--render-markdown",
+        ]);
+        let mut state = super::super::ServerState::try_new(&opts)?;
+        let dirs = [(String::new(), state.mounts[0].path.clone())];
+        state.livereload = Some(super::super::LiveReload::try_new(&dirs)?);
+        let url = spawn(super::super::build_router(Arc::new(state))?).await?;
+
+        // 渲染与注入脚本后的内容与清单不一致，下载时需要取得原始文件
+        let out = tempfile::tempdir()?;
+        let summary = process_http_fetch(&url, out.path(), None).await?;
+        assert_eq!(summary.downloaded, 3);
+        for name in ["README.md", "index.html", "data.csv"] {
+            assert_eq!(
+                std::fs::read(out.path().join(name))?,
+                std::fs::read(src.path().join(name))?
+            );
+        }
+
+        let err = process_http_fetch("ftp://localhost/", out.path(), None).await;
+        assert!(err.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fetch_rejects_tampered_files() -> Result<()> {
+        let manifest = serde_json::json!({
+            "algorithm": "blake3",
+            "files": [
+                {"path": "a.txt", "size": 5, "hash": blake3::hash(b"hello").to_hex().as_str()},
+                {"path": "b.txt", "size": 5, "hash": blake3::hash(b"world").to_hex().as_str()},
+                {"path": "../c.txt", "size": 5, "hash": blake3::hash(b"hello").to_hex().as_str()},
+            ]
+        });
+        let router = Router::new()
+            .route(
+                "/_manifest.json",
+                get_route(move || async move { manifest.to_string() }),
+            )
+            .route("/a.txt", get_route(|| async { "hello" }))
+            .route("/b.txt", get_route(|| async { "w0rld" }));
+        let url = spawn(router).await?;
+
+        let out = tempfile::tempdir()?;
+        let err = process_http_fetch(&url, out.path(), None)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("2 file(s) failed verification"));
+        assert!(err.contains("b.txt: hash mismatch"));
+        assert!(err.contains("../c.txt: unsafe path"));
+        assert!(out.path().join("a.txt").exists());
+        // 校验失败的文件不会留在输出目录中
+        let names: Vec<_> = std::fs::read_dir(out.path())?
+            .map(|e| e.map(|e| e.file_name()))
+            .collect::<std::io::Result<_>>()?;
+        assert_eq!(names, vec!["a.txt"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,337 @@
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use std::{io::SeekFrom, path::Path, time::SystemTime};
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt},
+};
+use tokio_util::io::ReaderStream;
+use tracing::{info, warn};
+
+use super::{
+    cache, compression,
+    range::{self, ByteRange},
+    HttpServeState,
+};
+
+// 开启--integrity时附带的摘要头
+pub(super) const REPR_DIGEST: HeaderName = HeaderName::from_static("repr-digest");
+const DIGEST: HeaderName = HeaderName::from_static("digest");
+// 内容嗅探时读取的最大字节数
+const SNIFF_LEN: usize = 512;
+// 流式读取文件时每个数据块的大小
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// 已打开的文件及其元信息
+struct OpenedFile {
+    // 文件句柄
+    file: File,
+    // 文件长度
+    len: u64,
+    // 推断出的MIME类型
+    mime: String,
+    // 最后修改时间
+    modified: Option<SystemTime>,
+    // 预压缩文件的Content-Encoding
+    encoding: Option<&'static str>,
+}
+
+// 以流的方式返回文件内容，并根据扩展名或文件内容推断Content-Type
+// 支持单个字节范围的Range/If-Range请求，返回206 Partial Content
+// 附带ETag/Last-Modified校验器，条件请求命中时返回304 Not Modified
+// 客户端支持时优先返回预压缩的同名文件（.br/.zst/.gz）
+pub(super) async fn serve_file(state: &HttpServeState, p: &Path, headers: &HeaderMap) -> Response {
+    let opened = match compression::find_precompressed(state, p, headers) {
+        Some((sibling, encoding)) => open_file(&sibling).await.map(|mut opened| {
+            info!("Serving precompressed {:?}", sibling);
+            // Content-Type以原始文件为准
+            opened.mime = guess_mime(p).unwrap_or_else(|| "application/octet-stream".to_string());
+            opened.encoding = Some(encoding);
+            (opened, sibling)
+        }),
+        None => open_file(p).await.map(|opened| (opened, p.to_path_buf())),
+    };
+    let (opened, source) = match opened {
+        Ok(opened) => opened,
+        Err(e) => return internal_error(e),
+    };
+    // 基于文件内容的blake3哈希生成强ETag
+    // 大文件的哈希在后台计算，计算完成前使用长度与修改时间生成的弱ETag，也不附带摘要头
+    let hash = match state
+        .hash_cache
+        .get_or_spawn(&source, opened.len, opened.modified)
+        .await
+    {
+        Ok(hash) => hash,
+        Err(e) => return internal_error(e),
+    };
+    let mut etag = match &hash {
+        Some(hash) => cache::etag(hash),
+        None => cache::weak_etag(opened.len, opened.modified),
+    };
+    if let Some(encoding) = opened.encoding {
+        etag = compression::encoded_etag(&etag, encoding);
+    }
+    let mut res = file_response(opened, &etag, state.cache_control.as_deref(), headers).await;
+    let Some(hash) = hash.filter(|_| state.integrity) else {
+        return res;
+    };
+    // 摘要针对实际发送的表示（预压缩文件为压缩后的内容），Range响应同样给出完整表示的摘要
+    let status = res.status();
+    if status.is_success() || status == StatusCode::NOT_MODIFIED {
+        let digests = [
+            (REPR_DIGEST, cache::repr_digest(&hash)),
+            (DIGEST, cache::digest(&hash)),
+        ];
+        for (name, value) in digests {
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                res.headers_mut().insert(name, value);
+            }
+        }
+    }
+    res
+}
+
+// 将文件内容渲染为HTML页面返回（Markdown、CSV/JSON/YAML预览）
+// ETag由文件哈希加上tag派生，与原始文件的ETag区分，同样支持条件请求
+// 渲染失败（例如内容无法解析）时按原始文件返回
+pub(super) async fn serve_rendered<F>(
+    state: &HttpServeState,
+    p: &Path,
+    headers: &HeaderMap,
+    tag: &str,
+    render: F,
+) -> Response
+where
+    F: FnOnce(&str, &str) -> anyhow::Result<String> + Send + 'static,
+{
+    let (content, meta) = match tokio::join!(tokio::fs::read(p), tokio::fs::metadata(p)) {
+        (Ok(content), Ok(meta)) => (content, meta),
+        (Err(e), _) | (_, Err(e)) => return internal_error(e),
+    };
+    let modified = meta.modified().ok();
+    let hash = match state
+        .hash_cache
+        .get_or_compute(p, meta.len(), modified)
+        .await
+    {
+        Ok(hash) => hash,
+        Err(e) => return internal_error(e),
+    };
+    let etag = format!("\"{}-{}\"", hash.to_hex(), tag);
+    let mut builder = Response::builder()
+        .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+        .header(header::ETAG, &etag);
+    if let Some(modified) = modified {
+        builder = builder.header(header::LAST_MODIFIED, httpdate::fmt_http_date(modified));
+    }
+    if let Some(cache_control) = &state.cache_control {
+        builder = builder.header(header::CACHE_CONTROL, cache_control);
+    }
+    if cache::is_not_modified(headers, &etag, modified) {
+        info!("Not modified");
+        return builder
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Body::empty())
+            .unwrap_or_else(|_| StatusCode::NOT_MODIFIED.into_response());
+    }
+    let name = p
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let content = String::from_utf8_lossy(&content).into_owned();
+    // 语法高亮、表格生成比较耗时，放到阻塞线程池中执行
+    let page = tokio::task::spawn_blocking(move || render(&content, &name)).await;
+    match page {
+        Ok(Ok(page)) => {
+            info!("Rendered {:?} as HTML", p);
+            builder
+                .body(Body::from(page))
+                .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+        Ok(Err(e)) => {
+            warn!("Error rendering {:?}, serving raw file: {}", p, e);
+            serve_file(state, p, headers).await
+        }
+        Err(e) => {
+            warn!("Error rendering {:?}: {:?}", p, e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+// 读取文件失败时返回500错误
+fn internal_error(e: std::io::Error) -> Response {
+    // 记录错误信息
+    warn!("Error reading file: {:?}", e);
+    // 返回500错误
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        format!("Error reading file: {:?}", e),
+    )
+        .into_response()
+}
+
+// 根据条件请求头和请求的字节范围构建响应
+async fn file_response(
+    opened: OpenedFile,
+    etag: &str,
+    cache_control: Option<&str>,
+    headers: &HeaderMap,
+) -> Response {
+    let OpenedFile {
+        mut file,
+        len,
+        mime,
+        modified,
+        encoding,
+    } = opened;
+    let mut builder = Response::builder()
+        .header(header::CONTENT_TYPE, mime)
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::ETAG, etag);
+    if let Some(encoding) = encoding {
+        builder = builder
+            .header(header::CONTENT_ENCODING, encoding)
+            .header(header::VARY, header::ACCEPT_ENCODING.as_str());
+    }
+    if let Some(modified) = modified {
+        builder = builder.header(header::LAST_MODIFIED, httpdate::fmt_http_date(modified));
+    }
+    if let Some(cache_control) = cache_control {
+        builder = builder.header(header::CACHE_CONTROL, cache_control);
+    }
+    if cache::is_not_modified(headers, etag, modified) {
+        info!("Not modified");
+        return builder
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Body::empty())
+            .unwrap_or_else(|_| StatusCode::NOT_MODIFIED.into_response());
+    }
+    let response = match range::requested_range(headers, len, modified, Some(etag)) {
+        ByteRange::Full => {
+            info!("Serving {} bytes", len);
+            builder
+                .status(StatusCode::OK)
+                .header(header::CONTENT_LENGTH, len)
+                .body(Body::from_stream(ReaderStream::with_capacity(
+                    file, CHUNK_SIZE,
+                )))
+        }
+        ByteRange::Partial(start, end) => {
+            info!("Serving bytes {}-{} of {}", start, end, len);
+            if let Err(e) = file.seek(SeekFrom::Start(start)).await {
+                warn!("Error seeking file: {:?}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+            let size = end - start + 1;
+            builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", start, end, len),
+                )
+                .header(header::CONTENT_LENGTH, size)
+                .body(Body::from_stream(ReaderStream::with_capacity(
+                    file.take(size),
+                    CHUNK_SIZE,
+                )))
+        }
+        ByteRange::Unsatisfiable => builder
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+            .body(Body::empty()),
+    };
+    response.unwrap_or_else(|e| {
+        warn!("Error building response: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })
+}
+
+// 打开文件并推断MIME类型
+async fn open_file(p: &Path) -> std::io::Result<OpenedFile> {
+    let mut file = File::open(p).await?;
+    let meta = file.metadata().await?;
+    let mime = match guess_mime(p) {
+        Some(mime) => mime,
+        None => {
+            // 扩展名无法识别时，读取文件开头的内容进行嗅探
+            let mut buf = Vec::with_capacity(SNIFF_LEN);
+            (&mut file)
+                .take(SNIFF_LEN as u64)
+                .read_to_end(&mut buf)
+                .await?;
+            file.seek(SeekFrom::Start(0)).await?;
+            sniff_mime(&buf)
+        }
+    };
+    Ok(OpenedFile {
+        file,
+        len: meta.len(),
+        mime,
+        modified: meta.modified().ok(),
+        encoding: None,
+    })
+}
+
+// 根据扩展名推断MIME类型
+pub(super) fn guess_mime(p: &Path) -> Option<String> {
+    mime_guess::from_path(p)
+        .first()
+        .map(|mime| with_charset(mime.essence_str()))
+}
+
+// 根据文件开头的字节推断MIME类型
+fn sniff_mime(buf: &[u8]) -> String {
+    if let Some(kind) = infer::get(buf) {
+        return kind.mime_type().to_string();
+    }
+    match std::str::from_utf8(buf) {
+        Ok(_) => with_charset("text/plain"),
+        // 截断在多字节字符中间时error_len为None，仍视为文本
+        Err(e) if e.error_len().is_none() => with_charset("text/plain"),
+        Err(_) => "application/octet-stream".to_string(),
+    }
+}
+
+// 文本类型统一补充UTF-8字符集
+fn with_charset(mime: &str) -> String {
+    if mime.starts_with("text/") {
+        format!("{}; charset=utf-8", mime)
+    } else {
+        mime.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_mime() {
+        assert_eq!(sniff_mime(b"hello world"), "text/plain; charset=utf-8");
+        assert_eq!(sniff_mime(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), "image/png");
+        assert_eq!(
+            sniff_mime(&[0xff, 0x00, 0xfe, 0x01]),
+            "application/octet-stream"
+        );
+        // 多字节字符被截断
+        assert_eq!(
+            sniff_mime(&"你好".as_bytes()[..4]),
+            "text/plain; charset=utf-8"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_open_file_guesses_mime() -> anyhow::Result<()> {
+        let opened = open_file(Path::new("fixtures/index.html")).await?;
+        assert_eq!(opened.mime, "text/html; charset=utf-8");
+        assert_eq!(opened.len, std::fs::metadata("fixtures/index.html")?.len());
+        let opened = open_file(Path::new("fixtures/ed25519.sk")).await?;
+        assert_eq!(opened.mime, "application/octet-stream");
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+use axum::http::StatusCode;
+use std::{
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+};
+
+use super::upload;
+
+// 将请求路径解析为服务根目录下的实际路径
+// root必须是已经规范化（canonicalize）的绝对路径
+// - 包含".."、绝对路径或盘符前缀的请求直接拒绝（403）
+// - 未开启follow_symlinks时，规范化后的路径必须仍位于root之内，
+//   因此指向根目录之外的符号链接同样被拒绝（403）
+// - 路径不存在时返回404，上传中的临时文件同样视为不存在
+pub(super) fn resolve_path(
+    root: &Path,
+    req_path: &str,
+    follow_symlinks: bool,
+) -> Result<PathBuf, StatusCode> {
+    let relative = sanitize(req_path)?;
+    if upload::is_temp_file(&relative) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let joined = root.join(relative);
+    if follow_symlinks {
+        return if joined.exists() {
+            Ok(joined)
+        } else {
+            Err(StatusCode::NOT_FOUND)
+        };
+    }
+    match joined.canonicalize() {
+        Ok(p) if p.starts_with(root) => Ok(p),
+        Ok(_) => Err(StatusCode::FORBIDDEN),
+        Err(e) if e.kind() == ErrorKind::NotFound => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::FORBIDDEN),
+    }
+}
+
+// 将写入请求的路径解析为服务根目录下的目标文件路径
+// 目标文件可以不存在，但其所在目录必须已经存在（否则返回409）且同样受根目录限制
+pub(super) fn resolve_new_path(
+    root: &Path,
+    req_path: &str,
+    follow_symlinks: bool,
+) -> Result<PathBuf, StatusCode> {
+    let relative = sanitize(req_path)?;
+    let Some(name) = relative.file_name() else {
+        return Err(StatusCode::FORBIDDEN);
+    };
+    let parent = relative
+        .parent()
+        .and_then(Path::to_str)
+        .ok_or(StatusCode::FORBIDDEN)?;
+    let dir = match resolve_path(root, parent, follow_symlinks) {
+        Ok(dir) if dir.is_dir() => dir,
+        Ok(_) | Err(StatusCode::NOT_FOUND) => return Err(StatusCode::CONFLICT),
+        Err(status) => return Err(status),
+    };
+    Ok(dir.join(name))
+}
+
+// 对请求路径做词法检查，只保留普通路径段
+fn sanitize(req_path: &str) -> Result<PathBuf, StatusCode> {
+    if req_path.contains('\0') {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let mut relative = PathBuf::new();
+    for component in Path::new(req_path).components() {
+        match component {
+            Component::Normal(seg) => relative.push(seg),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(StatusCode::FORBIDDEN)
+            }
+        }
+    }
+    Ok(relative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::test_util::{get_status, test_router};
+    use anyhow::Result;
+
+    #[test]
+    fn test_sanitize() {
+        assert_eq!(sanitize("a/./b/"), Ok(PathBuf::from("a/b")));
+        assert_eq!(sanitize(""), Ok(PathBuf::new()));
+        assert_eq!(sanitize("../a"), Err(StatusCode::FORBIDDEN));
+        assert_eq!(sanitize("a/../b"), Err(StatusCode::FORBIDDEN));
+        assert_eq!(sanitize("/etc/passwd"), Err(StatusCode::FORBIDDEN));
+        assert_eq!(sanitize("a\0b"), Err(StatusCode::FORBIDDEN));
+    }
+
+    #[test]
+    fn test_resolve_new_path() {
+        let root = Path::new("fixtures").canonicalize().unwrap();
+        assert_eq!(
+            resolve_new_path(&root, "new.txt", false),
+            Ok(root.join("new.txt"))
+        );
+        assert_eq!(
+            resolve_new_path(&root, "missing/new.txt", false),
+            Err(StatusCode::CONFLICT)
+        );
+        assert_eq!(
+            resolve_new_path(&root, "b64.txt/new.txt", false),
+            Err(StatusCode::CONFLICT)
+        );
+        assert_eq!(
+            resolve_new_path(&root, "../new.txt", false),
+            Err(StatusCode::FORBIDDEN)
+        );
+        assert_eq!(
+            resolve_new_path(&root, "", false),
+            Err(StatusCode::FORBIDDEN)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_path_traversal_rejected() {
+        let router = test_router(&["--dir", "fixtures"]);
+        assert_eq!(get_status(&router, "/b64.txt").await, StatusCode::OK);
+        assert_eq!(
+            get_status(&router, "/missing.txt").await,
+            StatusCode::NOT_FOUND
+        );
+        for uri in [
+            "/../Cargo.toml",
+            "/%2e%2e/Cargo.toml",
+            "/%2E%2E/Cargo.toml",
+            "/.%2e/Cargo.toml",
+            "/%2e%2e%2fCargo.toml",
+            "/..%2fCargo.toml",
+            "/b64.txt/%2e%2e/%2e%2e/Cargo.toml",
+            "/%2e%2e/%2e%2e/%2e%2e/etc/passwd",
+            "/%2fetc%2fpasswd",
+        ] {
+            assert_eq!(
+                get_status(&router, uri).await,
+                StatusCode::FORBIDDEN,
+                "{}",
+                uri
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlink_outside_root() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let root = tmp.path().join("root");
+        std::fs::create_dir(&root)?;
+        std::fs::write(root.join("inside.txt"), "inside")?;
+        std::fs::write(tmp.path().join("outside.txt"), "outside")?;
+        std::os::unix::fs::symlink(tmp.path().join("outside.txt"), root.join("link.txt"))?;
+        std::os::unix::fs::symlink("inside.txt", root.join("inner-link.txt"))?;
+        let root = root.to_str().unwrap();
+
+        let router = test_router(&["--dir", root]);
+        assert_eq!(get_status(&router, "/inside.txt").await, StatusCode::OK);
+        assert_eq!(get_status(&router, "/inner-link.txt").await, StatusCode::OK);
+        assert_eq!(
+            get_status(&router, "/link.txt").await,
+            StatusCode::FORBIDDEN
+        );
+        // 不存在绕过路径校验的静态文件路由
+        for uri in ["/tower/link.txt", "/tower/inside.txt"] {
+            assert_eq!(
+                get_status(&router, uri).await,
+                StatusCode::NOT_FOUND,
+                "{}",
+                uri
+            );
+        }
+
+        let router = test_router(&["--dir", root, "--follow-symlinks"]);
+        assert_eq!(get_status(&router, "/link.txt").await, StatusCode::OK);
+        assert_eq!(
+            get_status(&router, "/%2e%2e/outside.txt").await,
+            StatusCode::FORBIDDEN
+        );
+        Ok(())
+    }
+}
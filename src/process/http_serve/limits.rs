@@ -0,0 +1,344 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_server::accept::Accept;
+use futures::future::BoxFuture;
+use std::{
+    collections::HashMap,
+    io,
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    sync::{OwnedSemaphorePermit, Semaphore},
+};
+use tracing::warn;
+
+use super::ServerState;
+use crate::RateLimit;
+
+// 记录的客户端数量超过该值时清理已经回满的令牌桶
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+// 限制并发连接数的acceptor，连接数达到上限时直接关闭新连接
+// 许可随连接一起释放，TLS握手前就已占用许可
+#[derive(Debug, Clone)]
+pub(super) struct ConnectionLimit<A> {
+    inner: A,
+    semaphore: Option<Arc<Semaphore>>,
+}
+
+impl<A> ConnectionLimit<A> {
+    pub(super) fn new(inner: A, max_connections: Option<usize>) -> Self {
+        Self {
+            inner,
+            semaphore: max_connections.map(|n| Arc::new(Semaphore::new(n))),
+        }
+    }
+}
+
+impl<A, I, S> Accept<I, S> for ConnectionLimit<A>
+where
+    A: Accept<I, S> + Clone + Send + 'static,
+    A::Future: Send,
+    I: Send + 'static,
+    S: Send + 'static,
+{
+    type Stream = LimitedStream<A::Stream>;
+    type Service = A::Service;
+    type Future = BoxFuture<'static, io::Result<(Self::Stream, Self::Service)>>;
+
+    fn accept(&self, stream: I, service: S) -> Self::Future {
+        let inner = self.inner.clone();
+        let semaphore = self.semaphore.clone();
+        Box::pin(async move {
+            let permit = match semaphore {
+                Some(semaphore) => match semaphore.try_acquire_owned() {
+                    Ok(permit) => Some(permit),
+                    Err(e) => {
+                        // 返回错误后连接被丢弃，socket随之关闭
+                        warn!("Connection limit reached, closing new connection");
+                        return Err(io::Error::other(e));
+                    }
+                },
+                None => None,
+            };
+            let (stream, service) = inner.accept(stream, service).await?;
+            Ok((
+                LimitedStream {
+                    inner: stream,
+                    _permit: permit,
+                },
+                service,
+            ))
+        })
+    }
+}
+
+// 持有连接许可的数据流，连接关闭时释放许可
+#[derive(Debug)]
+pub(super) struct LimitedStream<I> {
+    inner: I,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl<I: AsyncRead + Unpin> AsyncRead for LimitedStream<I> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<I: AsyncWrite + Unpin> AsyncWrite for LimitedStream<I> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+}
+
+// 按客户端IP计数的令牌桶限流器
+#[derive(Debug)]
+pub(super) struct RateLimiter {
+    // 令牌桶容量，即一个周期内允许的请求数
+    capacity: f64,
+    // 每秒补充的令牌数
+    refill_rate: f64,
+    // 每个IP当前的令牌数与上次更新时间
+    buckets: Mutex<HashMap<IpAddr, (f64, Instant)>>,
+}
+
+impl RateLimiter {
+    pub(super) fn new(limit: RateLimit) -> Self {
+        let capacity = f64::from(limit.requests);
+        Self {
+            capacity,
+            refill_rate: capacity / limit.per.as_secs_f64(),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // 消耗一个令牌，令牌不足时返回需要等待的时间
+    fn check(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        let Ok(mut buckets) = self.buckets.lock() else {
+            return Ok(());
+        };
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            let full = Duration::from_secs_f64(self.capacity / self.refill_rate);
+            buckets.retain(|_, (_, last)| now.duration_since(*last) < full);
+        }
+        let (tokens, last) = buckets.entry(ip).or_insert((self.capacity, now));
+        *tokens = (*tokens + now.duration_since(*last).as_secs_f64() * self.refill_rate)
+            .min(self.capacity);
+        *last = now;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - *tokens) / self.refill_rate))
+        }
+    }
+}
+
+// 限流中间件，超出频率限制的请求返回429并附带Retry-After
+pub(super) async fn rate_limit(
+    State(state): State<Arc<ServerState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let Some(limiter) = &state.rate_limiter else {
+        return next.run(req).await;
+    };
+    let Some(ConnectInfo(addr)) = req.extensions().get::<ConnectInfo<SocketAddr>>() else {
+        return next.run(req).await;
+    };
+    match limiter.check(addr.ip(), Instant::now()) {
+        Ok(()) => next.run(req).await,
+        Err(wait) => {
+            warn!("Rate limit exceeded for {}", addr.ip());
+            let mut res = (StatusCode::TOO_MANY_REQUESTS, "Too many requests").into_response();
+            let secs = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+            res.headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+            res
+        }
+    }
+}
+
+// 免除请求超时的标记，由请求超时中间件放入请求扩展中
+#[derive(Debug, Clone, Default)]
+pub(super) struct TimeoutExemption(Arc<AtomicBool>);
+
+// 标记当前请求不受--request-timeout限制，用于在处理函数中读取整个请求体的上传与WebDAV写入
+pub(super) fn exempt_from_timeout(req: &Request) {
+    if let Some(TimeoutExemption(exempt)) = req.extensions().get::<TimeoutExemption>() {
+        exempt.store(true, Ordering::Relaxed);
+    }
+}
+
+// 中间件形式的exempt_from_timeout，添加到上传路由上
+pub(super) async fn skip_timeout(req: Request, next: Next) -> Response {
+    exempt_from_timeout(&req);
+    next.run(req).await
+}
+
+// 请求超时中间件，在超时时间内没有生成响应头时返回408
+// 响应体的传输不受限制；路由匹配到上传或WebDAV写入时整个请求都不受超时限制
+pub(super) async fn request_timeout(
+    State(timeout): State<Duration>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let exemption = TimeoutExemption::default();
+    req.extensions_mut().insert(exemption.clone());
+    let res = next.run(req);
+    tokio::pin!(res);
+    tokio::select! {
+        res = &mut res => res,
+        _ = tokio::time::sleep(timeout) => {
+            if exemption.0.load(Ordering::Relaxed) {
+                res.await
+            } else {
+                (StatusCode::REQUEST_TIMEOUT, "Request timed out").into_response()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::test_util::test_router;
+    use anyhow::Result;
+    use axum::{body::Body, http::Request, Router};
+    use axum_server::accept::DefaultAcceptor;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_connection_limit() {
+        let limit = ConnectionLimit::new(DefaultAcceptor, Some(1));
+        let (first, _) = limit.accept((), ()).await.unwrap();
+        // 达到上限时拒绝新连接，而不是排队等待
+        assert!(limit.accept((), ()).await.is_err());
+        drop(first);
+        assert!(limit.accept((), ()).await.is_ok());
+        let unlimited = ConnectionLimit::new(DefaultAcceptor, None);
+        let _held: Vec<_> = futures::future::try_join_all((0..3).map(|_| unlimited.accept((), ())))
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::new(RateLimit {
+            requests: 2,
+            per: Duration::from_secs(1),
+        });
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+        let other: IpAddr = "127.0.0.2".parse().unwrap();
+        let now = Instant::now();
+        assert!(limiter.check(ip, now).is_ok());
+        assert!(limiter.check(ip, now).is_ok());
+        let wait = limiter.check(ip, now).unwrap_err();
+        assert!(wait > Duration::ZERO && wait <= Duration::from_millis(500));
+        assert!(limiter.check(other, now).is_ok());
+        assert!(limiter.check(ip, now + Duration::from_millis(500)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rate_and_body_limits() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let router = test_router(&[
+            "--dir",
+            tmp.path().to_str().unwrap(),
+            "--upload",
+            "--rate-limit",
+            "2/m",
+            "--max-body-size",
+            "4",
+        ]);
+        let request = |ip: [u8; 4]| {
+            let mut req = Request::get("/").body(Body::empty()).unwrap();
+            req.extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from((ip, 1234))));
+            req
+        };
+        for _ in 0..2 {
+            let res = router.clone().oneshot(request([10, 0, 0, 1])).await?;
+            assert_eq!(res.status(), StatusCode::OK);
+        }
+        let res = router.clone().oneshot(request([10, 0, 0, 1])).await?;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(res.headers().contains_key(header::RETRY_AFTER));
+        let res = router.clone().oneshot(request([10, 0, 0, 2])).await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = Request::put("/a.txt")
+            .header(header::CONTENT_LENGTH, 5)
+            .body(Body::from("hello"))?;
+        let res = router.oneshot(req).await?;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_proxy_timeout() -> Result<()> {
+        let backend = Router::new().fallback(|| async {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            "late"
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move { axum::serve(listener, backend).await });
+
+        // 只有上传与WebDAV写入不受超时限制，代理的POST请求仍然会超时
+        let route = format!("/api=http://{}", addr);
+        let router = test_router(&[
+            "--dir",
+            "fixtures",
+            "--proxy",
+            &route,
+            "--request-timeout",
+            "100ms",
+        ]);
+        let req = Request::post("/api/slow").body(Body::from("hello"))?;
+        let res = router.oneshot(req).await?;
+        assert_eq!(res.status(), StatusCode::REQUEST_TIMEOUT);
+        Ok(())
+    }
+}
@@ -0,0 +1,309 @@
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Json, Response},
+};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use serde::Serialize;
+use std::{
+    fmt::Write as _,
+    path::Path,
+    time::{Duration, UNIX_EPOCH},
+};
+use tracing::warn;
+
+use super::{guard, HttpServeState};
+
+// URL路径段中需要转义的字符集合
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+// 目录中的一个条目（文件或子目录）
+#[derive(Debug, Serialize)]
+pub(super) struct DirEntryInfo {
+    // 条目名称
+    pub name: String,
+    // 是否为目录
+    pub is_dir: bool,
+    // 文件大小（字节），目录为0
+    pub size: u64,
+    // 最后修改时间（Unix时间戳，秒）
+    pub modified: Option<u64>,
+}
+
+// 目录列表的JSON表示
+#[derive(Debug, Serialize)]
+struct DirListing<'a> {
+    // 请求的目录路径
+    path: String,
+    // 目录中的条目
+    entries: &'a [DirEntryInfo],
+}
+
+// 生成目录列表响应：客户端要求JSON时返回JSON，否则返回HTML页面
+// 开启上传时在HTML页面中附带上传表单
+pub(super) async fn list_directory(
+    state: &HttpServeState,
+    dir: &Path,
+    req_path: &str,
+    headers: &HeaderMap,
+) -> Response {
+    let entries = match read_dir_entries(&state.path, dir, state.follow_symlinks).await {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("Error reading directory: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Error reading directory: {:?}", e),
+            )
+                .into_response();
+        }
+    };
+    let url_path = dir_url_path(req_path);
+    if wants_json(headers) {
+        Json(DirListing {
+            path: url_path,
+            entries: &entries,
+        })
+        .into_response()
+    } else {
+        Html(render_html(&url_path, &entries, state.upload)).into_response()
+    }
+}
+
+// 读取目录内容，目录排在前面，同类按名称排序
+// 与文件请求使用同样的路径检查，隐藏无法访问的条目（根目录之外的符号链接、上传中的临时文件）
+pub(super) async fn read_dir_entries(
+    root: &Path,
+    dir: &Path,
+    follow_symlinks: bool,
+) -> std::io::Result<Vec<DirEntryInfo>> {
+    let mut entries = Vec::new();
+    let mut rd = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = rd.next_entry().await? {
+        let path = entry.path();
+        let accessible = path.strip_prefix(root).is_ok_and(|rel| {
+            guard::resolve_path(root, &rel.to_string_lossy(), follow_symlinks).is_ok()
+        });
+        if !accessible {
+            continue;
+        }
+        // metadata会跟随符号链接，失效的链接直接跳过
+        let Ok(meta) = tokio::fs::metadata(&path).await else {
+            continue;
+        };
+        let modified = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
+        entries.push(DirEntryInfo {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            is_dir: meta.is_dir(),
+            size: if meta.is_dir() { 0 } else { meta.len() },
+            modified,
+        });
+    }
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+    Ok(entries)
+}
+
+// 判断客户端是否要求JSON格式（Accept中包含application/json且不接受HTML）
+fn wants_json(headers: &HeaderMap) -> bool {
+    let Some(accept) = headers.get(header::ACCEPT).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    let accept: Vec<_> = accept
+        .split(',')
+        .map(|t| t.split(';').next().unwrap_or("").trim())
+        .collect();
+    accept.contains(&"application/json") && !accept.contains(&"text/html")
+}
+
+// 将请求路径规范为以"/"开头和结尾的目录URL
+pub(super) fn dir_url_path(req_path: &str) -> String {
+    let trimmed = req_path.trim_matches('/');
+    if trimmed.is_empty() {
+        "/".to_string()
+    } else {
+        format!("/{}/", trimmed)
+    }
+}
+
+// 对路径的每一段进行百分号编码，生成可用于href的URL
+pub(super) fn encode_url_path(path: &str) -> String {
+    path.split('/')
+        .map(|seg| utf8_percent_encode(seg, PATH_SEGMENT).to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// 转义HTML特殊字符
+pub(super) fn html_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// 将字节数格式化为易读的大小
+fn format_size(size: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", size, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+// 渲染目录列表的HTML页面
+fn render_html(url_path: &str, entries: &[DirEntryInfo], upload: bool) -> String {
+    let title = html_escape(url_path);
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Index of {title}</title>\
+         <style>body{{font-family:sans-serif;margin:2em}}table{{border-collapse:collapse}}\
+         td,th{{padding:2px 12px;text-align:left}}td.size{{text-align:right}}</style></head>\
+         <body><h1>Index of {title}</h1>\
+         <p>Download: <a href=\"?download=zip\">zip</a> <a href=\"?download=tar.gz\">tar.gz</a></p><table>\
+         <tr><th>Name</th><th>Size</th><th>Modified</th></tr>"
+    );
+    // 使用绝对路径链接，避免请求路径缺少结尾"/"时相对链接解析错误
+    if let Some((parent, _)) = url_path.trim_end_matches('/').rsplit_once('/') {
+        let _ = write!(
+            html,
+            "<tr><td><a href=\"{}/\">../</a></td><td></td><td></td></tr>",
+            html_escape(&encode_url_path(parent))
+        );
+    }
+    for entry in entries {
+        let suffix = if entry.is_dir { "/" } else { "" };
+        let href = encode_url_path(&format!("{}{}", url_path, entry.name));
+        let size = if entry.is_dir {
+            "-".to_string()
+        } else {
+            format_size(entry.size)
+        };
+        let modified = entry
+            .modified
+            .map(|secs| httpdate::fmt_http_date(UNIX_EPOCH + Duration::from_secs(secs)))
+            .unwrap_or_default();
+        let _ = write!(
+            html,
+            "<tr><td><a href=\"{href}{suffix}\">{name}{suffix}</a></td>\
+             <td class=\"size\">{size}</td><td>{modified}</td></tr>",
+            href = html_escape(&href),
+            name = html_escape(&entry.name),
+        );
+    }
+    html.push_str("</table>");
+    if upload {
+        let _ = write!(
+            html,
+            "<form method=\"post\" action=\"{}\" enctype=\"multipart/form-data\">\
+             <input type=\"file\" name=\"file\" multiple> <button type=\"submit\">Upload</button></form>",
+            html_escape(&encode_url_path(url_path))
+        );
+    }
+    html.push_str("</body></html>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::test_util::{get_status, test_router};
+    use axum::{
+        body::{to_bytes, Body},
+        http::{HeaderValue, Request},
+        Router,
+    };
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_wants_json() {
+        let mut headers = HeaderMap::new();
+        assert!(!wants_json(&headers));
+        headers.insert(header::ACCEPT, HeaderValue::from_static("application/json"));
+        assert!(wants_json(&headers));
+        headers.insert(
+            header::ACCEPT,
+            HeaderValue::from_static("text/html,application/json;q=0.9"),
+        );
+        assert!(!wants_json(&headers));
+    }
+
+    #[tokio::test]
+    async fn test_read_dir_entries() -> anyhow::Result<()> {
+        let root = Path::new("fixtures").canonicalize()?;
+        let entries = read_dir_entries(&root, &root, false).await?;
+        let entry = entries.iter().find(|e| e.name == "b64.txt").unwrap();
+        assert!(!entry.is_dir);
+        assert!(entry.size > 0);
+        assert!(entry.modified.is_some());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_listing_hides_inaccessible_entries() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let root = tmp.path().join("root");
+        std::fs::create_dir(&root)?;
+        std::fs::write(root.join("inside.txt"), "inside")?;
+        std::fs::write(root.join(".a.txt.0123456789abcdef.rcli-upload"), "partial")?;
+        std::fs::write(tmp.path().join("outside.txt"), "outside")?;
+        std::os::unix::fs::symlink(tmp.path().join("outside.txt"), root.join("link.txt"))?;
+        let root = root.to_str().unwrap();
+        let names = |router: Router| async move {
+            let req = Request::get("/")
+                .header(header::ACCEPT, "application/json")
+                .body(Body::empty())?;
+            let body = to_bytes(router.oneshot(req).await?.into_body(), usize::MAX).await?;
+            let listing: serde_json::Value = serde_json::from_slice(&body)?;
+            let names: Vec<_> = listing["entries"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|e| e["name"].as_str().unwrap().to_string())
+                .collect();
+            Ok::<_, anyhow::Error>(names)
+        };
+
+        // 列表中只显示文件请求可以访问的条目
+        let router = test_router(&["--dir", root]);
+        assert_eq!(names(router.clone()).await?, ["inside.txt"]);
+        assert_eq!(
+            get_status(&router, "/.a.txt.0123456789abcdef.rcli-upload").await,
+            StatusCode::NOT_FOUND
+        );
+
+        let router = test_router(&["--dir", root, "--follow-symlinks"]);
+        assert_eq!(names(router).await?, ["inside.txt", "link.txt"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,229 @@
+use anyhow::Result;
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Query, Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use futures::{stream, Stream};
+use http_body::Body as _;
+use notify::{recommended_watcher, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{convert::Infallible, fmt, path::PathBuf, sync::Arc, time::Duration};
+use tokio::sync::broadcast::{self, error::TryRecvError};
+use tracing::{info, warn};
+
+use super::{upload::is_temp_file, ServeParams, ServerState};
+
+// 浏览器订阅文件变更事件的路径
+pub(super) const LIVERELOAD_PATH: &str = "/_rcli/livereload";
+
+// 注入到HTML页面中的脚本，收到变更事件后刷新页面
+const RELOAD_SCRIPT: &str = r#"<script>(function(){var s=new EventSource("/_rcli/livereload");s.addEventListener("reload",function(){location.reload()});})();</script>"#;
+
+// 注入脚本时允许缓冲的最大HTML大小，更大的页面原样返回
+const MAX_INJECT_SIZE: usize = 8 * 1024 * 1024;
+
+// 合并连续文件事件的等待时间
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+// 监听服务目录的文件变更，并广播给所有已连接的浏览器
+pub(super) struct LiveReload {
+    tx: broadcast::Sender<String>,
+    // 保存监听器，释放后停止监听
+    _watcher: RecommendedWatcher,
+}
+
+impl fmt::Debug for LiveReload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LiveReload")
+            .field("receivers", &self.tx.receiver_count())
+            .finish_non_exhaustive()
+    }
+}
+
+impl LiveReload {
+    // 递归监听所有挂载点的目录，忽略上传过程中产生的临时文件
+    // dirs为挂载的路径前缀与对应的目录
+    pub(super) fn try_new(dirs: &[(String, PathBuf)]) -> Result<Self> {
+        let (tx, _) = broadcast::channel(16);
+        let sender = tx.clone();
+        let mounts = dirs.to_vec();
+        let mut watcher = recommended_watcher(move |res: notify::Result<notify::Event>| {
+            let event = match res {
+                Ok(event) => event,
+                Err(e) => return warn!("Error watching files: {:?}", e),
+            };
+            if !matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+            ) {
+                return;
+            }
+            let Some(path) = event.paths.iter().find(|p| !is_temp_file(p)) else {
+                return;
+            };
+            info!("File changed: {:?}", path);
+            // 只向浏览器发送文件的URL路径；没有浏览器连接时发送失败，直接忽略
+            let url = mounts
+                .iter()
+                .find_map(|(base, dir)| {
+                    let rel = path.strip_prefix(dir).ok()?;
+                    Some(format!("{}/{}", base, rel.display()))
+                })
+                .unwrap_or_default();
+            let _ = sender.send(url);
+        })?;
+        for (_, dir) in dirs {
+            watcher.watch(dir, RecursiveMode::Recursive)?;
+        }
+        Ok(Self {
+            tx,
+            _watcher: watcher,
+        })
+    }
+
+    // 订阅文件变更事件
+    fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.tx.subscribe()
+    }
+}
+
+// 通过Server-Sent Events推送文件变更通知
+pub(super) async fn events_handler(State(state): State<Arc<ServerState>>) -> Response {
+    let Some(livereload) = &state.livereload else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    Sse::new(reload_events(livereload.subscribe()))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+// 将广播的变更转换为reload事件流，接收过慢丢失的消息同样触发刷新
+// 一次保存通常产生多个文件事件，短暂等待后合并为一次刷新
+fn reload_events(rx: broadcast::Receiver<String>) -> impl Stream<Item = Result<Event, Infallible>> {
+    stream::unfold(rx, |mut rx| async move {
+        let path = match rx.recv().await {
+            Ok(path) => path,
+            Err(broadcast::error::RecvError::Lagged(_)) => String::new(),
+            Err(broadcast::error::RecvError::Closed) => return None,
+        };
+        tokio::time::sleep(DEBOUNCE).await;
+        while !matches!(
+            rx.try_recv(),
+            Err(TryRecvError::Empty | TryRecvError::Closed)
+        ) {}
+        Some((Ok(Event::default().event("reload").data(path)), rx))
+    })
+}
+
+// 在完整的HTML响应中注入刷新脚本
+// 已压缩、部分内容、超过MAX_INJECT_SIZE或长度未知的响应保持不变，raw=1的请求同样返回原始内容
+pub(super) async fn inject_script(req: Request, next: Next) -> Response {
+    let raw = Query::<ServeParams>::try_from_uri(req.uri()).is_ok_and(|Query(p)| p.raw());
+    let res = next.run(req).await;
+    if raw {
+        return res;
+    }
+    let is_html = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/html"));
+    if res.status() != StatusCode::OK
+        || !is_html
+        || res.headers().contains_key(header::CONTENT_ENCODING)
+        || !fits_inject_size(&res)
+    {
+        return res;
+    }
+    let (mut parts, body) = res.into_parts();
+    let html = match to_bytes(body, MAX_INJECT_SIZE).await {
+        Ok(html) => html,
+        Err(e) => {
+            warn!("Error reading HTML for live reload: {:?}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Error reading file").into_response();
+        }
+    };
+    let html = inject(&String::from_utf8_lossy(&html));
+    // 内容已改变，原有的长度与校验信息不再有效
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.remove(header::ETAG);
+    parts.headers.remove(header::ACCEPT_RANGES);
+    parts.headers.insert(
+        header::CACHE_CONTROL,
+        header::HeaderValue::from_static("no-cache"),
+    );
+    Response::from_parts(parts, Body::from(html))
+}
+
+// 根据Content-Length或响应体的大小提示判断能否缓冲整个页面
+fn fits_inject_size(res: &Response) -> bool {
+    let len = res
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+        .or_else(|| res.body().size_hint().upper());
+    len.is_some_and(|len| len <= MAX_INJECT_SIZE as u64)
+}
+
+// 将脚本插入到最后一个</body>之前，没有</body>时追加到末尾
+fn inject(html: &str) -> String {
+    match html.to_ascii_lowercase().rfind("</body>") {
+        Some(pos) => format!("{}{}{}", &html[..pos], RELOAD_SCRIPT, &html[pos..]),
+        None => format!("{}{}", html, RELOAD_SCRIPT),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{process::http_serve::build_router, HttpServeOpts};
+    use axum::http::Request;
+    use clap::Parser;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_inject() {
+        assert_eq!(
+            inject("<html><BODY>hi</BODY></html>"),
+            format!("<html><BODY>hi{}</BODY></html>", RELOAD_SCRIPT)
+        );
+        assert_eq!(inject("<p>hi</p>"), format!("<p>hi</p>{}", RELOAD_SCRIPT));
+    }
+
+    #[tokio::test]
+    async fn test_livereload_inject() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        std::fs::write(tmp.path().join("small.html"), "<body>hi</body>")?;
+        // 超过MAX_INJECT_SIZE的页面原样返回
+        let large = format!("<body>{}</body>", "a".repeat(9 * 1024 * 1024));
+        std::fs::write(tmp.path().join("large.html"), &large)?;
+        let opts = HttpServeOpts::parse_from(["serve", "--dir", tmp.path().to_str().unwrap()]);
+        let mut state = ServerState::try_new(&opts)?;
+        let dirs = [(String::new(), state.mounts[0].path.clone())];
+        state.livereload = Some(LiveReload::try_new(&dirs)?);
+        let router = build_router(Arc::new(state))?;
+
+        let res = router
+            .clone()
+            .oneshot(Request::get("/small.html").body(Body::empty())?)
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = to_bytes(res.into_body(), usize::MAX).await?;
+        assert!(String::from_utf8_lossy(&body).contains(LIVERELOAD_PATH));
+
+        let res = router
+            .oneshot(Request::get("/large.html").body(Body::empty())?)
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.headers().contains_key(header::ETAG));
+        let body = to_bytes(res.into_body(), usize::MAX).await?;
+        assert_eq!(body.len(), large.len());
+        Ok(())
+    }
+}
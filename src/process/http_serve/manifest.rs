@@ -0,0 +1,181 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use super::{archive, HttpServeState};
+use crate::process::text::TextSign;
+
+// 文件清单与其签名的路径，位于每个挂载点之下
+pub(super) const MANIFEST_PATH: &str = "_manifest.json";
+pub(super) const SIGNATURE_PATH: &str = "_manifest.json.sig";
+// 同时计算哈希的文件数
+const CONCURRENCY: usize = 8;
+
+// 挂载点内所有文件的清单，路径相对于清单所在的目录
+// rcli http fetch下载时按同样的结构解析
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct Manifest {
+    // 哈希算法，目前固定为blake3
+    pub(super) algorithm: String,
+    pub(super) files: Vec<ManifestEntry>,
+}
+
+// 清单中的一个文件
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct ManifestEntry {
+    // 以/分隔的相对路径
+    pub(super) path: String,
+    pub(super) size: u64,
+    // 十六进制的blake3哈希
+    pub(super) hash: String,
+}
+
+impl Manifest {
+    // 遍历挂载目录生成清单，文件哈希复用ETag使用的缓存，按修改时间与长度失效
+    pub(super) async fn build(state: &HttpServeState) -> anyhow::Result<Self> {
+        let root = state.path.clone();
+        let follow_symlinks = state.follow_symlinks;
+        let entries = tokio::task::spawn_blocking(move || {
+            archive::collect_entries(&root, &root, follow_symlinks)
+        })
+        .await??;
+        let mut files: Vec<ManifestEntry> = stream::iter(entries.into_iter().filter(|e| !e.is_dir))
+            .map(|entry| async move {
+                let hash = state
+                    .hash_cache
+                    .get_or_compute(&entry.path, entry.size, entry.modified)
+                    .await?;
+                Ok::<_, std::io::Error>(ManifestEntry {
+                    path: entry.name,
+                    size: entry.size,
+                    hash: hash.to_hex().to_string(),
+                })
+            })
+            .buffer_unordered(CONCURRENCY)
+            .try_collect()
+            .await?;
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(Self {
+            algorithm: "blake3".to_string(),
+            files,
+        })
+    }
+}
+
+// 返回挂载点的文件清单，关闭目录列表的挂载点不公开清单
+pub(super) async fn manifest_handler(State(state): State<Arc<HttpServeState>>) -> Response {
+    match manifest_bytes(&state).await {
+        Ok(bytes) => ([(header::CONTENT_TYPE, "application/json")], bytes).into_response(),
+        Err(res) => res,
+    }
+}
+
+// 返回清单的Ed25519分离签名，与rcli text sign的输出格式相同（URL安全的base64）
+// 清单内容按路径排序，文件未变化时两次生成的字节完全一致，签名可以单独请求
+pub(super) async fn signature_handler(State(state): State<Arc<HttpServeState>>) -> Response {
+    let Some(signer) = &state.manifest_signer else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let bytes = match manifest_bytes(&state).await {
+        Ok(bytes) => bytes,
+        Err(res) => return res,
+    };
+    match signer.sign(&mut bytes.as_slice()) {
+        Ok(signature) => URL_SAFE_NO_PAD.encode(signature).into_response(),
+        Err(e) => {
+            warn!("Error signing manifest: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+// 生成清单并序列化为JSON
+async fn manifest_bytes(state: &HttpServeState) -> Result<Vec<u8>, Response> {
+    if !state.listing {
+        return Err(StatusCode::FORBIDDEN.into_response());
+    }
+    let manifest = Manifest::build(state).await.map_err(|e| {
+        warn!("Error building manifest for {:?}: {:?}", state.path, e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+    info!("Serving manifest of {} files", manifest.files.len());
+    serde_json::to_vec(&manifest).map_err(|e| {
+        warn!("Error serializing manifest: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::test_util::{get_status, test_router};
+    use anyhow::Result;
+    use axum::{
+        body::{to_bytes, Body},
+        http::Request,
+    };
+    use base64::engine::general_purpose::STANDARD;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_integrity() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        std::fs::create_dir(tmp.path().join("sub"))?;
+        std::fs::write(tmp.path().join("sub/b.txt"), "b".repeat(4096))?;
+        std::fs::write(tmp.path().join("a.txt"), "hello")?;
+        let root = tmp.path().to_str().unwrap();
+        assert_eq!(
+            get_status(&test_router(&["--dir", root]), "/_manifest.json").await,
+            StatusCode::NOT_FOUND
+        );
+
+        let router = test_router(&["--dir", root, "--integrity"]);
+        let res = router
+            .clone()
+            .oneshot(Request::get("/a.txt").body(Body::empty())?)
+            .await?;
+        let hash = blake3::hash(b"hello");
+        let encoded = STANDARD.encode(hash.as_bytes());
+        assert_eq!(
+            res.headers()["repr-digest"],
+            format!("blake3=:{}:", encoded).as_str()
+        );
+        assert_eq!(
+            res.headers()["digest"],
+            format!("blake3={}", encoded).as_str()
+        );
+        // 带摘要的响应不再实时压缩
+        let req = Request::get("/sub/b.txt")
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())?;
+        let res = router.clone().oneshot(req).await?;
+        assert!(!res.headers().contains_key(header::CONTENT_ENCODING));
+
+        let res = router
+            .oneshot(Request::get("/_manifest.json").body(Body::empty())?)
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = to_bytes(res.into_body(), usize::MAX).await?;
+        let manifest: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(manifest["algorithm"], "blake3");
+        assert_eq!(
+            manifest["files"],
+            serde_json::json!([
+                {"path": "a.txt", "size": 5, "hash": hash.to_hex().as_str()},
+                {
+                    "path": "sub/b.txt",
+                    "size": 4096,
+                    "hash": blake3::hash("b".repeat(4096).as_bytes()).to_hex().as_str()
+                },
+            ])
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,360 @@
+use super::{file, listing::html_escape, preview::MAX_PREVIEW_SIZE, HttpServeState};
+use axum::{http::HeaderMap, response::Response};
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+use std::{collections::HashSet, fmt::Write as _, path::Path, sync::LazyLock};
+use syntect::{
+    highlighting::{Theme, ThemeSet},
+    html::highlighted_html_for_string,
+    parsing::SyntaxSet,
+};
+
+// 语法定义与配色只加载一次
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEME: LazyLock<Theme> = LazyLock::new(|| {
+    ThemeSet::load_defaults()
+        .themes
+        .remove("InspiredGitHub")
+        .unwrap_or_default()
+});
+
+// 渲染页面使用的样式
+const STYLE: &str = "\
+body{margin:0;font:16px/1.6 -apple-system,BlinkMacSystemFont,\"Segoe UI\",Helvetica,Arial,sans-serif;color:#1f2328;background:#fff}\
+main{display:flex;gap:2rem;max-width:1100px;margin:0 auto;padding:2rem 1rem}\
+nav.toc{flex:0 0 220px;position:sticky;top:1rem;align-self:flex-start;max-height:calc(100vh - 2rem);overflow:auto;font-size:14px}\
+nav.toc ul{list-style:none;margin:0;padding:0}\
+nav.toc a{color:#59636e;text-decoration:none}nav.toc a:hover{color:#0969da}\
+.toc-2{padding-left:1em}.toc-3{padding-left:2em}.toc-4,.toc-5,.toc-6{padding-left:3em}\
+article{flex:1;min-width:0}\
+h1,h2{border-bottom:1px solid #d1d9e0;padding-bottom:.3em}\
+a{color:#0969da}\
+pre{padding:1em;overflow:auto;border-radius:6px;background:#f6f8fa;font-size:85%}\
+code{font-family:ui-monospace,SFMono-Regular,Menlo,Consolas,monospace}\
+:not(pre)>code{padding:.2em .4em;border-radius:6px;background:#eff1f3;font-size:85%}\
+blockquote{margin:0;padding:0 1em;color:#59636e;border-left:.25em solid #d1d9e0}\
+table{border-collapse:collapse}th,td{padding:6px 13px;border:1px solid #d1d9e0}\
+img{max-width:100%}\
+@media (max-width:800px){main{display:block}nav.toc{position:static;margin-bottom:1rem}}";
+
+// 是否为Markdown文件
+pub(super) fn is_markdown(p: &Path) -> bool {
+    p.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown"))
+}
+
+// 将Markdown文件渲染为HTML页面返回
+// 超过MAX_PREVIEW_SIZE的文件不渲染，直接返回原始内容
+pub(super) async fn serve_markdown(
+    state: &HttpServeState,
+    p: &Path,
+    headers: &HeaderMap,
+) -> Response {
+    let too_large = tokio::fs::metadata(p)
+        .await
+        .is_ok_and(|m| m.len() > MAX_PREVIEW_SIZE);
+    if too_large {
+        return file::serve_file(state, p, headers).await;
+    }
+    file::serve_rendered(state, p, headers, "md", |content, name| {
+        Ok(render(content, name))
+    })
+    .await
+}
+
+// 目录中的一个标题
+struct TocEntry {
+    level: usize,
+    id: String,
+    text: String,
+}
+
+// 渲染完整的HTML页面：左侧为目录，右侧为正文，页面标题取第一个一级标题或文件名
+// Markdown中的原始HTML按文本转义输出，避免上传的文档在页面中执行脚本
+fn render(markdown: &str, name: &str) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_HEADING_ATTRIBUTES;
+    let mut events = Vec::new();
+    let mut toc = Vec::new();
+    let mut ids = HashSet::new();
+    // 正在处理的标题：开始事件的位置与标题文本
+    let mut heading: Option<(usize, String)> = None;
+    // 正在处理的代码块：语言与代码
+    let mut code: Option<(String, String)> = None;
+    for event in Parser::new_ext(markdown, options) {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().unwrap_or("").to_string()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code = Some((lang, String::new()));
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some((lang, text)) = code.take() {
+                    events.push(Event::Html(highlight(&text, &lang).into()));
+                }
+            }
+            Event::Text(text) if code.is_some() => {
+                if let Some((_, buf)) = code.as_mut() {
+                    buf.push_str(&text);
+                }
+            }
+            Event::Start(Tag::Heading { .. }) => {
+                heading = Some((events.len(), String::new()));
+                events.push(event);
+            }
+            Event::End(TagEnd::Heading(level)) => {
+                if let Some((start, text)) = heading.take() {
+                    if let Event::Start(Tag::Heading {
+                        id, classes, attrs, ..
+                    }) = &mut events[start]
+                    {
+                        // 只保留{#id}，丢弃自定义的class与属性，避免通过on*属性执行脚本
+                        classes.clear();
+                        attrs.clear();
+                        let slug = id
+                            .as_deref()
+                            .map(str::to_string)
+                            .unwrap_or_else(|| unique_slug(&text, &mut ids));
+                        *id = Some(CowStr::from(slug.clone()));
+                        toc.push(TocEntry {
+                            level: level as usize,
+                            id: slug,
+                            text,
+                        });
+                    }
+                }
+                events.push(event);
+            }
+            Event::Html(raw) | Event::InlineHtml(raw) => events.push(Event::Text(raw)),
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => events.push(Event::Start(Tag::Link {
+                link_type,
+                dest_url: safe_url(dest_url),
+                title,
+                id,
+            })),
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => events.push(Event::Start(Tag::Image {
+                link_type,
+                dest_url: safe_url(dest_url),
+                title,
+                id,
+            })),
+            Event::Text(ref text) | Event::Code(ref text) => {
+                if let Some((_, buf)) = heading.as_mut() {
+                    buf.push_str(text);
+                }
+                events.push(event);
+            }
+            _ => events.push(event),
+        }
+    }
+
+    let mut body = String::with_capacity(markdown.len() * 2);
+    html::push_html(&mut body, events.into_iter());
+    let title = toc
+        .iter()
+        .find(|entry| entry.level == 1)
+        .map_or(name, |entry| entry.text.as_str());
+    let mut page = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<main>\n",
+        html_escape(title),
+        STYLE
+    );
+    // 只有一个标题时不显示目录
+    if toc.len() > 1 {
+        page.push_str("<nav class=\"toc\">\n<ul>\n");
+        let top = toc.iter().map(|e| e.level).min().unwrap_or(1);
+        for entry in &toc {
+            let _ = writeln!(
+                page,
+                "<li class=\"toc-{}\"><a href=\"#{}\">{}</a></li>",
+                entry.level - top + 1,
+                html_escape(&entry.id),
+                html_escape(&entry.text)
+            );
+        }
+        page.push_str("</ul>\n</nav>\n");
+    }
+    let _ = write!(
+        page,
+        "<article>\n{}</article>\n</main>\n</body>\n</html>\n",
+        body
+    );
+    page
+}
+
+// 过滤链接与图片中可以执行脚本的地址，替换为"#"
+// 禁止javascript:、vbscript:与除图片以外的data:地址
+fn safe_url(url: CowStr<'_>) -> CowStr<'_> {
+    // 浏览器解析协议时会忽略其中的空白与控制字符
+    let scheme: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+        .take(16)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    let unsafe_scheme = scheme.starts_with("javascript:")
+        || scheme.starts_with("vbscript:")
+        || (scheme.starts_with("data:") && !scheme.starts_with("data:image/"));
+    if unsafe_scheme {
+        CowStr::Borrowed("#")
+    } else {
+        url
+    }
+}
+
+// 按语言高亮代码块，找不到对应语法时按纯文本输出
+fn highlight(code: &str, lang: &str) -> String {
+    let syntax = (!lang.is_empty())
+        .then(|| SYNTAXES.find_syntax_by_token(lang))
+        .flatten();
+    match syntax.map(|s| highlighted_html_for_string(code, &SYNTAXES, s, &THEME)) {
+        Some(Ok(html)) => html,
+        _ => format!("<pre><code>{}</code></pre>\n", html_escape(code)),
+    }
+}
+
+// 由标题文本生成锚点，保留字母与数字（包括中文），重复时追加序号
+fn unique_slug(text: &str, ids: &mut HashSet<String>) -> String {
+    let mut slug = String::new();
+    for c in text.trim().chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || c == '-' || c == '_') && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = match slug.trim_matches('-') {
+        "" => "section".to_string(),
+        s => s.to_string(),
+    };
+    let mut unique = slug.clone();
+    let mut n = 1;
+    while !ids.insert(unique.clone()) {
+        unique = format!("{}-{}", slug, n);
+        n += 1;
+    }
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::test_util::test_router;
+    use anyhow::Result;
+    use axum::{
+        body::{to_bytes, Body},
+        http::{header, Request, StatusCode},
+        Router,
+    };
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_render_markdown() {
+        let page = render(
+            "# 使用说明\n\nSee <script>alert(1)</script>\n\n## Install `rcli`\n\n```rust\nfn main() {}\n```\n\n## Install `rcli`\n",
+            "README.md",
+        );
+        assert!(page.contains("<title>使用说明</title>"));
+        assert!(page.contains("<h1 id=\"使用说明\">"));
+        assert!(
+            page.contains("<li class=\"toc-2\"><a href=\"#install-rcli\">Install rcli</a></li>")
+        );
+        assert!(page.contains("<h2 id=\"install-rcli-1\">"));
+        assert!(page.contains("&lt;script&gt;"));
+        assert!(!page.contains("<script>"));
+        // 高亮后的代码带有内联样式
+        assert!(page.contains("<span style=\"color:"));
+        assert!(is_markdown(Path::new("docs/README.MD")));
+
+        // 标题属性中只保留id
+        let page = render(
+            "# Hi {#hi .big onmouseover=alert(1) style=position:fixed;inset:0}",
+            "a.md",
+        );
+        assert!(page.contains("<h1 id=\"hi\">Hi</h1>"));
+        assert!(!page.contains("onmouseover"));
+        assert!(!page.contains("style=\"position"));
+
+        // 链接与图片中的脚本地址被替换
+        let page = render(
+            "[a](javascript:alert(1)) [b](JavaScript:alert(1)) \
+             [d](vbscript:msgbox) [e](data:text/html,<script>) ![f](data:image/png;base64,AA) \
+             [g](https://example.com) ![h](<java script:x>)",
+            "a.md",
+        );
+        assert!(!page.to_ascii_lowercase().contains("script:"));
+        assert!(!page.contains("data:text/html"));
+        assert_eq!(page.matches("href=\"#\"").count(), 4);
+        assert!(page.contains("src=\"data:image/png;base64,AA\""));
+        assert!(page.contains("href=\"https://example.com\""));
+        assert!(page.contains("src=\"#\""));
+        assert!(!is_markdown(Path::new("docs/README.txt")));
+    }
+
+    #[tokio::test]
+    async fn test_serve_markdown() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let content = "# Title\n\n## Usage\n\n```rust\nfn main() {}\n```\n";
+        std::fs::write(tmp.path().join("README.md"), content)?;
+        let root = tmp.path().to_str().unwrap();
+        let read = |router: Router, uri: &'static str| async move {
+            let res = router
+                .oneshot(Request::get(uri).body(Body::empty())?)
+                .await?;
+            let content_type = res.headers()[header::CONTENT_TYPE].clone();
+            let etag = res.headers()[header::ETAG].clone();
+            let body = to_bytes(res.into_body(), usize::MAX).await?;
+            Ok::<_, anyhow::Error>((content_type, etag, body))
+        };
+
+        // 未开启时按原样返回
+        let (_, _, body) = read(test_router(&["--dir", root]), "/README.md").await?;
+        assert_eq!(body, content.as_bytes());
+
+        let router = test_router(&["--dir", root, "--render-markdown"]);
+        let (content_type, etag, body) = read(router.clone(), "/README.md").await?;
+        assert_eq!(content_type, "text/html; charset=utf-8");
+        let page = String::from_utf8(body.to_vec())?;
+        assert!(page.contains("<title>Title</title>"));
+        assert!(page.contains("<a href=\"#usage\">Usage</a>"));
+        assert!(page.contains("<h2 id=\"usage\">"));
+        assert!(page.contains("<span style=\"color:"));
+
+        let (_, raw_etag, body) = read(router.clone(), "/README.md?raw=1").await?;
+        assert_eq!(body, content.as_bytes());
+        assert_ne!(etag, raw_etag);
+
+        let req = Request::get("/README.md")
+            .header(header::IF_NONE_MATCH, etag)
+            .body(Body::empty())?;
+        let res = router.clone().oneshot(req).await?;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        // 过大的文件不渲染
+        let large = "# Large\n".repeat(600 * 1024);
+        std::fs::write(tmp.path().join("large.md"), &large)?;
+        let (content_type, _, body) = read(router, "/large.md").await?;
+        assert_ne!(content_type, "text/html; charset=utf-8");
+        assert_eq!(body.len(), large.len());
+        Ok(())
+    }
+}
@@ -0,0 +1,413 @@
+use anyhow::{anyhow, Result};
+use axum::{
+    body::to_bytes,
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::Deserialize;
+use serde_json::json;
+use std::{collections::BTreeMap, net::SocketAddr, sync::Arc};
+use tracing::info;
+
+use super::{signed_url, ServerState};
+
+// 回显请求的内置路径
+pub(super) const ECHO_PATH: &str = "/_echo";
+
+// 回显的请求体最大大小
+const MAX_ECHO_BODY: usize = 2 * 1024 * 1024;
+
+// routes.yaml中的一条模拟路由
+//
+// - method: GET
+//   path: /api/users/{id}
+//   status: 200
+//   headers:
+//     X-Mock: "1"
+//   body:
+//     id: "{{id}}"
+//     name: Alice
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MockRoute {
+    // 请求方法，"*"匹配任意方法
+    #[serde(default = "default_method")]
+    method: String,
+    // 路径模式，{name}匹配一段路径，{*name}匹配剩余所有路径
+    path: String,
+    // 响应状态码
+    #[serde(default = "default_status")]
+    status: u16,
+    // 响应头
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    // 响应体，字符串原样返回，其他类型序列化为JSON；其中的{{name}}替换为路径参数
+    #[serde(default)]
+    body: serde_yaml::Value,
+}
+
+// 解析后的模拟路由
+#[derive(Debug)]
+struct Mock {
+    method: Option<Method>,
+    status: StatusCode,
+    headers: Vec<(HeaderName, HeaderValue)>,
+    body: serde_yaml::Value,
+}
+
+// 从routes.yaml加载的模拟路由表
+pub(super) struct MockRoutes {
+    mocks: Vec<Mock>,
+    // 路径模式到该路径上所有模拟路由下标的映射
+    router: matchit::Router<Vec<usize>>,
+}
+
+impl std::fmt::Debug for MockRoutes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockRoutes")
+            .field("mocks", &self.mocks)
+            .finish_non_exhaustive()
+    }
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+impl MockRoutes {
+    // 读取并校验路由文件，路径模式冲突时返回错误
+    pub(super) fn load(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::parse(&content)
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        let routes: Vec<MockRoute> = serde_yaml::from_str(content)?;
+        let mut mocks = Vec::with_capacity(routes.len());
+        let mut patterns: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for route in routes {
+            let method = match route.method.as_str() {
+                "*" => None,
+                m => Some(m.to_ascii_uppercase().parse::<Method>()?),
+            };
+            let headers = route
+                .headers
+                .iter()
+                .map(|(name, value)| Ok((name.parse()?, value.parse()?)))
+                .collect::<Result<Vec<_>>>()?;
+            patterns.entry(route.path).or_default().push(mocks.len());
+            mocks.push(Mock {
+                method,
+                status: StatusCode::from_u16(route.status)?,
+                headers,
+                body: route.body,
+            });
+        }
+        let mut router = matchit::Router::new();
+        for (pattern, indexes) in patterns {
+            router
+                .insert(pattern.as_str(), indexes)
+                .map_err(|e| anyhow!("Invalid mock path {:?}: {}", pattern, e))?;
+        }
+        Ok(Self { mocks, router })
+    }
+
+    // 按路径和方法查找模拟路由，返回路由与路径参数
+    fn find(&self, method: &Method, path: &str) -> Option<(&Mock, Vec<(String, String)>)> {
+        let matched = self.router.at(path).ok()?;
+        let mock = matched
+            .value
+            .iter()
+            .map(|&i| &self.mocks[i])
+            .find(|mock| mock.method.as_ref().is_none_or(|m| m == method))?;
+        let params = matched
+            .params
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Some((mock, params))
+    }
+}
+
+impl Mock {
+    // 生成模拟响应
+    fn response(&self, params: &[(String, String)]) -> Response {
+        let (content_type, body) = match render(&self.body, params) {
+            serde_yaml::Value::Null => ("text/plain; charset=utf-8", String::new()),
+            serde_yaml::Value::String(s) => ("text/plain; charset=utf-8", s),
+            value => (
+                "application/json",
+                serde_json::to_string(&value).unwrap_or_default(),
+            ),
+        };
+        let mut res = (self.status, body).into_response();
+        let headers = res.headers_mut();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+        for (name, value) in &self.headers {
+            headers.insert(name.clone(), value.clone());
+        }
+        res
+    }
+}
+
+// 将响应体中所有字符串里的{{name}}替换为路径参数
+fn render(value: &serde_yaml::Value, params: &[(String, String)]) -> serde_yaml::Value {
+    use serde_yaml::Value;
+    match value {
+        Value::String(s) => Value::String(params.iter().fold(s.clone(), |s, (k, v)| {
+            s.replace(&format!("{{{{{}}}}}", k), v)
+        })),
+        Value::Sequence(items) => {
+            Value::Sequence(items.iter().map(|v| render(v, params)).collect())
+        }
+        Value::Mapping(map) => Value::Mapping(
+            map.iter()
+                .map(|(k, v)| (render(k, params), render(v, params)))
+                .collect(),
+        ),
+        Value::Tagged(tagged) => render(&tagged.value, params),
+        other => other.clone(),
+    }
+}
+
+// 模拟路由中间件，匹配的请求直接返回模拟响应，其余请求交给静态文件等路由处理
+// 模拟路由使用所在挂载点的认证设置，配置了签名密钥时同样需要签名
+pub(super) async fn mock_routes(
+    State(state): State<Arc<ServerState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let Some(mocks) = &state.mocks else {
+        return next.run(req).await;
+    };
+    let Some((mock, params)) = mocks.find(req.method(), req.uri().path()) else {
+        return next.run(req).await;
+    };
+    // 与静态文件相同，先校验签名再校验认证
+    if !signed_url::is_signed(&state, req.uri()) {
+        return signed_url::forbidden(req.uri());
+    }
+    let credentials = &state.mount_for(req.uri().path()).credentials;
+    if !credentials.is_empty() && !credentials.authorize(req.headers()).await {
+        return credentials.challenge();
+    }
+    info!("Mock response for {} {}", req.method(), req.uri());
+    mock.response(&params)
+}
+
+// 以JSON格式回显请求的方法、路径、查询参数、请求头与请求体
+pub(super) async fn echo_handler(req: Request) -> Response {
+    let (parts, body) = req.into_parts();
+    let body = match to_bytes(body, MAX_ECHO_BODY).await {
+        Ok(body) => body,
+        Err(_) => {
+            return (StatusCode::PAYLOAD_TOO_LARGE, "Request body is too large").into_response()
+        }
+    };
+    let remote = parts
+        .extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.to_string());
+    let (method, uri, headers) = (parts.method, parts.uri, parts.headers);
+    let query: BTreeMap<String, String> = uri
+        .query()
+        .map(|q| {
+            url_decode_pairs(q)
+                .into_iter()
+                .collect::<BTreeMap<String, String>>()
+        })
+        .unwrap_or_default();
+    let headers: BTreeMap<&str, String> = headers
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.as_str(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })
+        .collect();
+    // 非UTF-8的请求体以base64返回
+    let (body, encoding) = match std::str::from_utf8(&body) {
+        Ok(body) => (body.to_string(), "utf-8"),
+        Err(_) => (STANDARD.encode(&body), "base64"),
+    };
+    Json(json!({
+        "method": method.as_str(),
+        "path": uri.path(),
+        "query": query,
+        "headers": headers,
+        "body": body,
+        "body_encoding": encoding,
+        "remote": remote,
+    }))
+    .into_response()
+}
+
+// 解析查询字符串，重复的参数保留最后一个
+fn url_decode_pairs(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+            let decode = |s: &str| {
+                percent_encoding::percent_decode_str(&s.replace('+', " "))
+                    .decode_utf8_lossy()
+                    .into_owned()
+            };
+            (decode(k), decode(v))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::test_util::{get_status, test_router};
+    use axum::{body::Body, http::Request};
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_mock_routes() -> Result<()> {
+        let mocks = MockRoutes::parse(
+            r#"
+- path: /api/users/{id}
+  body:
+    id: "{{id}}"
+    tags: ["user-{{id}}"]
+- method: DELETE
+  path: /api/users/{id}
+  status: 204
+- method: "*"
+  path: /api/files/{*rest}
+  headers:
+    X-Mock: "1"
+  body: "file {{rest}}"
+"#,
+        )?;
+        let (mock, params) = mocks.find(&Method::GET, "/api/users/42").unwrap();
+        assert_eq!(params, vec![("id".to_string(), "42".to_string())]);
+        let body = render(&mock.body, &params);
+        assert_eq!(
+            serde_json::to_value(&body)?,
+            json!({"id": "42", "tags": ["user-42"]})
+        );
+        let (mock, _) = mocks.find(&Method::DELETE, "/api/users/42").unwrap();
+        assert_eq!(mock.status, StatusCode::NO_CONTENT);
+        assert!(mocks.find(&Method::PUT, "/api/users/42").is_none());
+        let (mock, params) = mocks.find(&Method::POST, "/api/files/a/b.txt").unwrap();
+        assert_eq!(
+            render(&mock.body, &params),
+            serde_yaml::Value::String("file a/b.txt".to_string())
+        );
+        assert!(mocks.find(&Method::GET, "/other").is_none());
+
+        assert!(MockRoutes::parse("- path: /{a}\n- path: /{b}\n").is_err());
+        assert!(MockRoutes::parse("- path: /a\n  status: 1000\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_url_decode_pairs() {
+        assert_eq!(
+            url_decode_pairs("a=1&b=hello+world&c=%E4%BD%A0&d"),
+            vec![
+                ("a".to_string(), "1".to_string()),
+                ("b".to_string(), "hello world".to_string()),
+                ("c".to_string(), "你".to_string()),
+                ("d".to_string(), String::new()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mock_and_echo() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let routes = tmp.path().join("routes.yaml");
+        std::fs::write(
+            &routes,
+            "- path: /b64.txt\n  body: mocked\n- method: POST\n  path: /api/users/{id}\n  \
+             status: 201\n  body:\n    id: \"{{id}}\"\n",
+        )?;
+        let router = test_router(&["--dir", "fixtures", "--mock", routes.to_str().unwrap()]);
+        let body = |res: Response| async { to_bytes(res.into_body(), usize::MAX).await.unwrap() };
+
+        // 模拟路由优先于静态文件
+        let res = router
+            .clone()
+            .oneshot(Request::get("/b64.txt").body(Body::empty())?)
+            .await?;
+        assert_eq!(&body(res).await[..], b"mocked");
+        let res = router
+            .clone()
+            .oneshot(Request::post("/api/users/7").body(Body::empty())?)
+            .await?;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(&body(res).await[..], br#"{"id":"7"}"#);
+        assert_eq!(get_status(&router, "/index.html").await, StatusCode::OK);
+
+        // 未开启模拟模式或--echo时没有回显端点
+        let plain = test_router(&["--dir", "fixtures"]);
+        assert_eq!(get_status(&plain, "/_echo").await, StatusCode::NOT_FOUND);
+
+        let req = Request::put("/_echo?a=1")
+            .header("x-test", "yes")
+            .body(Body::from("hello"))?;
+        let res = router.oneshot(req).await?;
+        let echo: serde_json::Value = serde_json::from_slice(&body(res).await)?;
+        assert_eq!(echo["method"], "PUT");
+        assert_eq!(echo["path"], "/_echo");
+        assert_eq!(echo["query"]["a"], "1");
+        assert_eq!(echo["headers"]["x-test"], "yes");
+        assert_eq!(echo["body"], "hello");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mock_mount_auth() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let routes = tmp.path().join("routes.yaml");
+        std::fs::write(
+            &routes,
+            "- path: /private/api\n  body: secret\n- path: /api\n  body: public\n",
+        )?;
+        let config = tmp.path().join("rcli-serve.toml");
+        let fixtures = std::fs::canonicalize("fixtures")?;
+        std::fs::write(
+            &config,
+            format!(
+                "[[mount]]\npath = \"/private\"\ndir = {:?}\nauth = [\"alice:secret\"]\n",
+                fixtures
+            ),
+        )?;
+        let router = test_router(&[
+            "--dir",
+            "fixtures",
+            "--mock",
+            routes.to_str().unwrap(),
+            "--config",
+            config.to_str().unwrap(),
+        ]);
+        // 挂载点下的模拟路由使用该挂载点的认证设置
+        assert_eq!(get_status(&router, "/api").await, StatusCode::OK);
+        assert_eq!(
+            get_status(&router, "/private/api").await,
+            StatusCode::UNAUTHORIZED
+        );
+        let req = Request::get("/private/api")
+            .header(header::AUTHORIZATION, "Basic YWxpY2U6c2VjcmV0")
+            .body(Body::empty())?;
+        let res = router.oneshot(req).await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(&to_bytes(res.into_body(), usize::MAX).await?[..], b"secret");
+        Ok(())
+    }
+}
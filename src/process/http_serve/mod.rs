@@ -0,0 +1,746 @@
+mod access_log;
+mod admin;
+mod archive;
+mod auth;
+mod cache;
+mod compression;
+mod config;
+mod cors;
+mod fetch;
+mod file;
+mod guard;
+mod limits;
+mod listing;
+mod livereload;
+mod manifest;
+mod markdown;
+mod mock;
+mod preview;
+mod proxy;
+mod range;
+mod signed_url;
+mod site;
+#[cfg(test)]
+mod test_util;
+mod tls;
+mod upload;
+mod webdav;
+
+use anyhow::{anyhow, Result};
+use axum::{
+    extract::{DefaultBodyLimit, Path, Query, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, Uri},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{any, get, post, put},
+    Router,
+};
+use std::{
+    collections::HashSet,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    process::text::{Ed25519Signer, KeyLoader},
+    AdminEndpoint, CompressionAlgorithm, HttpServeOpts, MountPoint,
+};
+use access_log::AccessLog;
+use admin::Metrics;
+use auth::Credentials;
+use axum_server::{accept::DefaultAcceptor, Handle};
+use cache::HashCache;
+use chrono::{DateTime, Local};
+use config::{MountConfig, ServeConfig};
+use limits::{ConnectionLimit, RateLimiter};
+use livereload::LiveReload;
+use mock::MockRoutes;
+use proxy::Proxy;
+use serde::Deserialize;
+use signed_url::UrlSigner;
+use tower_http::{
+    limit::RequestBodyLimitLayer,
+    set_header::SetResponseHeaderLayer,
+    trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer},
+    LatencyUnit,
+};
+use tracing::{info, warn, Level};
+use webdav::WebDav;
+
+pub use fetch::{process_http_fetch, FetchSummary};
+pub use signed_url::process_http_sign_url;
+
+// 挂载点的服务状态，根目录与每个--mount/配置文件中的挂载点各有一份
+#[derive(Debug)]
+struct HttpServeState {
+    // 服务的根路径（已规范化）
+    path: PathBuf,
+    // 挂载的路径前缀，根目录为空字符串
+    base: String,
+    // 是否允许跟随指向根目录之外的符号链接
+    follow_symlinks: bool,
+    // 文件响应附带的Cache-Control头
+    cache_control: Option<String>,
+    // 文件内容哈希缓存，用于生成ETag
+    hash_cache: HashCache,
+    // 访问凭据，为空时不需要认证
+    credentials: Credentials,
+    // 附加到响应的头部
+    headers: Vec<(HeaderName, HeaderValue)>,
+    // 是否返回目录列表
+    listing: bool,
+    // 是否允许上传文件
+    upload: bool,
+    // 单个上传文件的最大字节数
+    max_upload_size: u64,
+    // 上传与WebDAV写入时是否禁止覆盖已有文件
+    no_clobber: bool,
+    // 目录打包下载的最大总大小
+    max_archive_size: u64,
+    // 是否优先返回预压缩的同名文件
+    precompressed: bool,
+    // 目录索引文件名，未设置时返回目录列表
+    index: Option<String>,
+    // 是否开启单页应用回退
+    spa: bool,
+    // 自定义404页面
+    not_found_page: Option<PathBuf>,
+    // 是否允许省略.html扩展名
+    clean_urls: bool,
+    // WebDAV处理器，开启--webdav时提供读写访问
+    webdav: Option<WebDav>,
+    // 是否附带摘要头并提供文件清单
+    integrity: bool,
+    // 文件清单的Ed25519签名密钥，设置后同时提供清单与分离签名
+    manifest_signer: Option<Ed25519Signer>,
+    // 是否将Markdown文件渲染为HTML页面
+    render_markdown: bool,
+    // 是否为浏览器生成CSV/JSON/YAML文件的预览页面
+    preview: bool,
+}
+
+// 整个服务共享的状态
+#[derive(Debug)]
+struct ServerState {
+    // 所有挂载点，第一个为根目录
+    mounts: Vec<Arc<HttpServeState>>,
+    // 签名链接校验器，设置后只允许带有效签名的请求
+    url_signer: Option<UrlSigner>,
+    // 启用的实时压缩算法，为空时不压缩
+    compress: Vec<CompressionAlgorithm>,
+    // 启用实时压缩的最小响应大小
+    compress_min_size: u16,
+    // CORS允许的来源与方法，未启用CORS时为None
+    cors: Option<(Vec<String>, Vec<String>)>,
+    // 访问日志，未配置时为None
+    access_log: Option<AccessLog>,
+    // 文件变更监听，开启--watch时用于通知浏览器刷新
+    livereload: Option<LiveReload>,
+    // 反向代理路由
+    proxies: Vec<Proxy>,
+    // 按客户端IP的请求频率限制
+    rate_limiter: Option<RateLimiter>,
+    // 单个请求的超时时间
+    request_timeout: Option<Duration>,
+    // 请求体的最大大小
+    max_body_size: Option<u64>,
+    // 模拟路由
+    mocks: Option<MockRoutes>,
+    // 是否提供/_echo回显端点
+    echo: bool,
+    // 启用的管理端点
+    admin: Vec<AdminEndpoint>,
+    // 管理端点的单独端口
+    admin_port: Option<u16>,
+    // 请求指标，启用metrics端点时采集
+    metrics: Option<Arc<Metrics>>,
+    // 服务启动的时刻，用于计算运行时间
+    started: Instant,
+    // 服务启动的时间
+    started_at: DateTime<Local>,
+}
+
+impl HttpServeState {
+    // 创建挂载点状态，配置文件中未设置的选项沿用命令行参数
+    fn try_new(
+        opts: &HttpServeOpts,
+        base: &str,
+        dir: &std::path::Path,
+        mount: Option<&MountConfig>,
+    ) -> Result<Self> {
+        let credentials = match mount.filter(|m| m.has_auth()) {
+            Some(m) => Credentials::try_new(
+                m.auth.as_deref().unwrap_or_default(),
+                m.auth_file.as_deref(),
+                m.tokens.as_deref().unwrap_or_default(),
+            )?,
+            None => Credentials::try_new(
+                &opts.auth,
+                opts.auth_file.as_deref().map(std::path::Path::new),
+                &opts.token,
+            )?,
+        };
+        let mut headers = opts
+            .header
+            .iter()
+            .map(|h| {
+                let (name, value) = h
+                    .split_once(':')
+                    .ok_or_else(|| anyhow!("Invalid header {:?}, expected \"Name: value\"", h))?;
+                parse_header(name, value)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for (name, value) in mount.iter().flat_map(|m| &m.headers) {
+            let (name, value) = parse_header(name, value)?;
+            headers.retain(|(n, _)| *n != name);
+            headers.push((name, value));
+        }
+        let path = dir.canonicalize()?;
+        let follow_symlinks = mount
+            .and_then(|m| m.follow_symlinks)
+            .unwrap_or(opts.follow_symlinks);
+        let webdav = mount
+            .and_then(|m| m.webdav)
+            .unwrap_or(opts.webdav)
+            .then(|| WebDav::new(&path, base, follow_symlinks));
+        Ok(Self {
+            path,
+            base: base.to_string(),
+            follow_symlinks,
+            cache_control: mount
+                .and_then(|m| m.cache_control.clone())
+                .or_else(|| opts.cache_control.clone()),
+            hash_cache: HashCache::default(),
+            credentials,
+            headers,
+            listing: mount.and_then(|m| m.listing).unwrap_or(!opts.no_listing),
+            upload: mount.and_then(|m| m.upload).unwrap_or(opts.upload),
+            max_upload_size: opts.max_upload_size,
+            no_clobber: opts.no_clobber,
+            max_archive_size: opts.max_archive_size,
+            precompressed: !opts.no_precompressed,
+            index: mount
+                .and_then(|m| m.index.clone())
+                .or_else(|| opts.index.clone()),
+            spa: opts.spa,
+            not_found_page: opts.not_found_page.as_ref().map(PathBuf::from),
+            clean_urls: opts.clean_urls,
+            webdav,
+            integrity: opts.integrity,
+            manifest_signer: opts
+                .sign_manifest
+                .as_deref()
+                .map(Ed25519Signer::load)
+                .transpose()?,
+            render_markdown: opts.render_markdown,
+            preview: !opts.no_preview,
+        })
+    }
+
+    // 将挂载点内的相对路径转换为相对服务根的路径，用于生成链接
+    fn url_path(&self, path: &str) -> String {
+        if self.base.is_empty() {
+            path.to_string()
+        } else {
+            format!("{}/{}", self.base.trim_start_matches('/'), path)
+        }
+    }
+}
+
+impl ServerState {
+    // 请求路径所属的挂载点，取匹配的最长前缀，都不匹配时为根目录
+    fn mount_for(&self, path: &str) -> &Arc<HttpServeState> {
+        self.mounts
+            .iter()
+            .skip(1)
+            .filter(|m| {
+                path.strip_prefix(m.base.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .max_by_key(|m| m.base.len())
+            .unwrap_or(&self.mounts[0])
+    }
+
+    fn try_new(opts: &HttpServeOpts) -> Result<Self> {
+        let config = opts
+            .config
+            .as_deref()
+            .map(ServeConfig::load)
+            .transpose()?
+            .unwrap_or_default();
+        // 配置文件中path为"/"的挂载点替换根目录的设置
+        let root = config.mounts.iter().find(|m| m.path.trim() == "/");
+        let root_dir = root.map(|m| m.dir.as_path()).unwrap_or(&opts.dir);
+        let mut mounts = vec![Arc::new(HttpServeState::try_new(opts, "", root_dir, root)?)];
+        for mount in &opts.mount {
+            mounts.push(Arc::new(HttpServeState::try_new(
+                opts,
+                &mount.prefix,
+                &mount.dir,
+                None,
+            )?));
+        }
+        for mount in config.mounts.iter().filter(|m| m.path.trim() != "/") {
+            let point: MountPoint = format!("{}={}", mount.path, mount.dir.display()).parse()?;
+            mounts.push(Arc::new(HttpServeState::try_new(
+                opts,
+                &point.prefix,
+                &point.dir,
+                Some(mount),
+            )?));
+        }
+        // 挂载点与代理的前缀不能重复
+        let mut prefixes = HashSet::new();
+        let proxy_prefixes = opts.proxy.iter().map(|p| p.prefix.as_str());
+        for prefix in mounts
+            .iter()
+            .skip(1)
+            .map(|m| m.base.as_str())
+            .chain(proxy_prefixes)
+        {
+            if !prefixes.insert(prefix) {
+                return Err(anyhow!("Duplicate mount or proxy prefix {:?}", prefix));
+            }
+        }
+        Ok(Self {
+            mounts,
+            url_signer: opts
+                .signed_urls_key
+                .as_deref()
+                .map(UrlSigner::load)
+                .transpose()?,
+            compress: if opts.no_compress {
+                Vec::new()
+            } else {
+                opts.compress.clone()
+            },
+            compress_min_size: opts.compress_min_size,
+            cors: opts
+                .cors
+                .then(|| (opts.cors_origin.clone(), opts.cors_methods.clone())),
+            access_log: opts
+                .access_log
+                .as_deref()
+                .map(|target| AccessLog::try_new(target, opts.access_log_format))
+                .transpose()?,
+            livereload: None,
+            proxies: opts.proxy.iter().cloned().map(Proxy::new).collect(),
+            rate_limiter: opts.rate_limit.map(RateLimiter::new),
+            request_timeout: opts.request_timeout,
+            max_body_size: opts.max_body_size,
+            mocks: opts.mock.as_deref().map(MockRoutes::load).transpose()?,
+            echo: opts.echo || opts.mock.is_some(),
+            admin: opts.admin.clone(),
+            admin_port: opts.admin_port,
+            metrics: opts
+                .admin
+                .contains(&AdminEndpoint::Metrics)
+                .then(|| Arc::new(Metrics::default())),
+            started: Instant::now(),
+            started_at: Local::now(),
+        })
+    }
+}
+
+// 解析响应头的名称和值
+fn parse_header(name: &str, value: &str) -> Result<(HeaderName, HeaderValue)> {
+    Ok((name.trim().parse()?, value.trim().parse()?))
+}
+
+// 处理HTTP服务的主函数
+pub async fn process_http_serve(opts: HttpServeOpts) -> Result<()> {
+    // 创建监听地址,绑定指定地址的指定端口
+    let addr = SocketAddr::new(opts.bind, opts.port);
+    // 创建服务状态实例
+    let mut state = ServerState::try_new(&opts)?;
+    if opts.watch {
+        let dirs: Vec<_> = state
+            .mounts
+            .iter()
+            .map(|m| (m.base.clone(), m.path.clone()))
+            .collect();
+        state.livereload = Some(LiveReload::try_new(&dirs)?);
+        info!("Watching for changes");
+    }
+    let tls_config = tls::load_tls_config(&opts).await?;
+    let scheme = if tls_config.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+    // 记录服务启动信息
+    for mount in &state.mounts {
+        info!(
+            "Serving {:?} on {}://{}{}/",
+            mount.path, scheme, addr, mount.base
+        );
+    }
+    let state = Arc::new(state);
+    let router = build_router(state.clone())?;
+    let service = router.into_make_service_with_connect_info::<SocketAddr>();
+    // 收到退出信号后停止接受新连接，并等待进行中的请求完成
+    let handle = Handle::new();
+    tokio::spawn(shutdown_signal(handle.clone(), opts.shutdown_timeout));
+
+    // 启动HTTP服务，同时支持HTTP/1.1与HTTP/2（TLS下通过ALPN协商）
+    let limit = ConnectionLimit::new(DefaultAcceptor, opts.max_connections);
+    let server = {
+        let handle = handle.clone();
+        async move {
+            match tls_config {
+                Some(config) => {
+                    axum_server::bind_rustls(addr, config)
+                        .map(|tls| tls.acceptor(limit))
+                        .handle(handle)
+                        .serve(service)
+                        .await
+                }
+                None => {
+                    axum_server::bind(addr)
+                        .acceptor(limit)
+                        .handle(handle)
+                        .serve(service)
+                        .await
+                }
+            }
+        }
+    };
+    // 管理端点使用单独的端口时，与文件服务一起启动和关闭
+    match opts.admin_port {
+        Some(port) => {
+            let admin_addr = SocketAddr::new(opts.bind, port);
+            info!("Serving admin endpoints on http://{}", admin_addr);
+            let admin = axum_server::bind(admin_addr)
+                .handle(handle)
+                .serve(admin::admin_router(state).into_make_service());
+            tokio::try_join!(server, admin)?;
+        }
+        None => server.await?,
+    }
+    info!("Server stopped");
+    // 返回成功
+    Ok(())
+}
+
+// 等待SIGINT（Ctrl-C）或SIGTERM，然后开始优雅关闭
+// 超过timeout仍未结束的连接会被强制关闭
+async fn shutdown_signal(handle: Handle<SocketAddr>, timeout: Duration) {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Error listening for Ctrl-C: {:?}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Error listening for SIGTERM: {:?}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    info!(
+        "Shutting down, waiting up to {:?} for {} connection(s)",
+        timeout,
+        handle.connection_count()
+    );
+    handle.graceful_shutdown(Some(timeout));
+}
+
+// 创建axum路由器
+fn build_router(state: Arc<ServerState>) -> Result<Router> {
+    let root = state.mounts[0].clone();
+    let mut router =
+        Router::new().route(livereload::LIVERELOAD_PATH, get(livereload::events_handler));
+    // 回显会返回请求头中的凭据，只在模拟模式或--echo时提供
+    if state.echo {
+        let mut echo = any(mock::echo_handler);
+        // 与模拟路由一样，配置了签名密钥时回显也需要签名
+        if state.url_signer.is_some() {
+            echo = echo.layer(middleware::from_fn_with_state(
+                state.clone(),
+                signed_url::require_signature,
+            ));
+        }
+        router = router.route(mock::ECHO_PATH, echo);
+    }
+    // 转发到后端的路由优先于静态文件
+    for proxy in &state.proxies {
+        let handler = {
+            let proxy = proxy.clone();
+            move |req| proxy.clone().forward(req)
+        };
+        router = router
+            .route(proxy.prefix(), any(handler.clone()))
+            .route(&format!("{}/{{*rest}}", proxy.prefix()), any(handler));
+    }
+    let mut router = router.with_state(state.clone());
+    // 没有单独的管理端口时，管理端点与文件服务共用端口并使用根目录的认证设置
+    if state.admin_port.is_none() {
+        router = router.merge(admin::admin_router(state.clone()));
+    }
+    // 代理等附加路由使用根目录的认证设置
+    if !root.credentials.is_empty() {
+        router = router.layer(middleware::from_fn_with_state(
+            root.clone(),
+            auth::require_auth,
+        ));
+    }
+    // 所有文件都经过挂载点的路由，统一校验路径、符号链接与认证
+    let mut files = Router::new();
+    for mount in &state.mounts {
+        files = files.merge(mount_router(mount.clone()));
+    }
+    // 配置了签名密钥时，文件请求都必须携带有效的签名
+    // 管理端点、实时刷新与代理路由不需要签名，模拟路由与回显单独校验签名
+    if state.url_signer.is_some() {
+        files = files.layer(middleware::from_fn_with_state(
+            state.clone(),
+            signed_url::require_signature,
+        ));
+    }
+    router = router.merge(files);
+    // 模拟路由优先于静态文件
+    if state.mocks.is_some() {
+        router = router.layer(middleware::from_fn_with_state(
+            state.clone(),
+            mock::mock_routes,
+        ));
+    }
+    // 开启--watch时向HTML页面注入自动刷新脚本，需要在压缩之前完成
+    if state.livereload.is_some() {
+        router = router.layer(middleware::from_fn(livereload::inject_script));
+    }
+    // 根据Accept-Encoding协商实时压缩，压缩后的响应使用弱ETag
+    if !state.compress.is_empty() {
+        router = router
+            .layer(compression::compression_layer(
+                &state.compress,
+                state.compress_min_size,
+            ))
+            .layer(middleware::from_fn(compression::weaken_etag));
+    }
+    // 处理跨域请求，预检请求在认证之前直接应答
+    if let Some((origins, methods)) = &state.cors {
+        router = router.layer(cors::cors_layer(origins, methods)?);
+    }
+    // 限制请求体大小
+    if let Some(max_body_size) = state.max_body_size {
+        let limit = usize::try_from(max_body_size).unwrap_or(usize::MAX);
+        router = router.layer(RequestBodyLimitLayer::new(limit));
+    }
+    // 请求超时
+    if let Some(timeout) = state.request_timeout {
+        router = router.layer(middleware::from_fn_with_state(
+            timeout,
+            limits::request_timeout,
+        ));
+    }
+    // 按客户端IP限流
+    if state.rate_limiter.is_some() {
+        router = router.layer(middleware::from_fn_with_state(
+            state.clone(),
+            limits::rate_limit,
+        ));
+    }
+    // 采集请求指标
+    if let Some(metrics) = &state.metrics {
+        router = router.layer(middleware::from_fn_with_state(
+            metrics.clone(),
+            admin::track_metrics,
+        ));
+    }
+    // 记录访问日志
+    if state.access_log.is_some() {
+        router = router.layer(middleware::from_fn_with_state(
+            state.clone(),
+            access_log::log_access,
+        ));
+    }
+    // 为每个请求创建tracing span，并记录响应状态与耗时
+    let trace = TraceLayer::new_for_http()
+        .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
+        .on_response(
+            DefaultOnResponse::new()
+                .level(Level::INFO)
+                .latency_unit(LatencyUnit::Millis),
+        );
+    Ok(router.layer(trace))
+}
+
+// 创建单个挂载点的路由，附带该挂载点的响应头与认证设置
+fn mount_router(state: Arc<HttpServeState>) -> Router {
+    let mut root_route = get(index_handler);
+    let mut path_route = get(file_handler);
+    // 开启上传时支持PUT与multipart表单POST
+    if state.upload {
+        // 上传大小在写入时按文件逐个限制，这里关闭axum默认的2MB请求体限制
+        // 上传在处理函数中读取整个请求体，不受--request-timeout限制
+        let limit = DefaultBodyLimit::disable();
+        let skip_timeout = middleware::from_fn(limits::skip_timeout);
+        root_route = root_route.merge(
+            post(upload::post_root_handler)
+                .route_layer(skip_timeout.clone())
+                .layer(limit),
+        );
+        path_route = path_route.merge(
+            put(upload::put_handler)
+                .post(upload::post_handler)
+                .route_layer(skip_timeout)
+                .layer(limit),
+        );
+    }
+    let mut router = if state.base.is_empty() {
+        Router::new().route("/", root_route)
+    } else {
+        Router::new()
+            .route(&state.base, root_route.clone())
+            .route(&format!("{}/", state.base), root_route)
+    }
+    .route(&format!("{}/{{*path}}", state.base), path_route);
+    // 文件清单的静态路由优先于通配路由匹配
+    if state.integrity || state.manifest_signer.is_some() {
+        router = router.route(
+            &format!("{}/{}", state.base, manifest::MANIFEST_PATH),
+            get(manifest::manifest_handler),
+        );
+    }
+    if state.manifest_signer.is_some() {
+        router = router.route(
+            &format!("{}/{}", state.base, manifest::SIGNATURE_PATH),
+            get(manifest::signature_handler),
+        );
+    }
+    router = router
+        // WebDAV方法在路由匹配之后、方法分发之前处理，因此不会被返回405
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            webdav::handle,
+        ));
+    for (name, value) in &state.headers {
+        router = router.layer(SetResponseHeaderLayer::overriding(
+            name.clone(),
+            value.clone(),
+        ));
+    }
+    // 配置了凭据时，挂载点的所有请求都需要先通过认证
+    if !state.credentials.is_empty() {
+        router = router.layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_auth,
+        ));
+    }
+    router.with_state(state)
+}
+
+// 文件与目录请求的查询参数
+#[derive(Debug, Default, Deserialize)]
+struct ServeParams {
+    // 打包下载的格式：zip或tar.gz
+    download: Option<String>,
+    // raw=1返回文件的原始内容，不渲染Markdown、不生成预览、不注入刷新脚本
+    raw: Option<String>,
+}
+
+impl ServeParams {
+    fn raw(&self) -> bool {
+        self.raw
+            .as_deref()
+            .is_some_and(|v| v != "0" && v != "false")
+    }
+}
+
+// 处理根路径请求，返回服务根目录的列表
+async fn index_handler(
+    State(state): State<Arc<HttpServeState>>,
+    uri: Uri,
+    headers: HeaderMap,
+) -> Response {
+    serve_path(&state, "", &uri, &headers).await
+}
+
+// 处理文件请求的处理函数
+async fn file_handler(
+    // 获取共享状态
+    State(state): State<Arc<HttpServeState>>,
+    // 获取请求路径
+    Path(path): Path<String>,
+    // 获取完整的请求URI，用于读取查询参数
+    uri: Uri,
+    // 获取请求头
+    headers: HeaderMap,
+) -> Response {
+    serve_path(&state, &path, &uri, &headers).await
+}
+
+// 根据请求路径返回文件内容、目录列表或目录的打包下载
+async fn serve_path(
+    state: &HttpServeState,
+    path: &str,
+    uri: &Uri,
+    headers: &HeaderMap,
+) -> Response {
+    // 解析并校验文件路径，防止访问服务目录之外的文件
+    let p = match site::resolve(state, path) {
+        Ok(p) => p,
+        Err(StatusCode::NOT_FOUND) => {
+            // 文件不存在时返回404，或按配置回退到入口文件/自定义404页面
+            return site::not_found(state, path, headers).await;
+        }
+        Err(status) => {
+            warn!("Rejected request for {:?}", path);
+            return (status, format!("Access to {:?} is forbidden", path)).into_response();
+        }
+    };
+    // 记录读取文件的日志
+    info!("Reading file {:?}", p);
+    let params = Query::<ServeParams>::try_from_uri(uri)
+        .map(|Query(params)| params)
+        .unwrap_or_default();
+    if !p.is_dir() {
+        if state.render_markdown && !params.raw() && markdown::is_markdown(&p) {
+            return markdown::serve_markdown(state, &p, headers).await;
+        }
+        if let Some(kind) = preview::PreviewKind::from_path(&p).filter(|_| state.preview) {
+            // 浏览器请求时返回预览页面，其他客户端得到原始文件
+            if !params.raw() && preview::wants_html(headers) {
+                return preview::serve_preview(state, &p, kind, headers).await;
+            }
+            let mut res = file::serve_file(state, &p, headers).await;
+            preview::vary_accept(&mut res);
+            return res;
+        }
+        // 以流的方式返回文件内容
+        return file::serve_file(state, &p, headers).await;
+    }
+    if let Some(format) = params.download {
+        // 打包下载会暴露目录内容，与目录列表使用同一开关
+        if !state.listing {
+            return (StatusCode::FORBIDDEN, "Directory listing is disabled").into_response();
+        }
+        return match format.parse() {
+            Ok(format) => archive::serve_archive(state, &p, format).await,
+            Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+        };
+    }
+    // 目录优先返回索引文件，否则返回文件/子目录列表
+    match site::serve_index(state, path, headers).await {
+        Some(res) => res,
+        None if state.listing => {
+            listing::list_directory(state, &p, &state.url_path(path), headers).await
+        }
+        None => (StatusCode::FORBIDDEN, "Directory listing is disabled").into_response(),
+    }
+}
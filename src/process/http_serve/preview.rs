@@ -0,0 +1,308 @@
+use anyhow::Result;
+use axum::{
+    http::{header, HeaderMap, HeaderValue},
+    response::Response,
+};
+use serde_json::Value;
+use std::{fmt::Write as _, path::Path};
+
+use super::{file, listing::html_escape, HttpServeState};
+use crate::process::csv_convert::read_csv;
+
+// 超过该大小的文件不生成预览或渲染Markdown，直接返回原始内容
+pub(super) const MAX_PREVIEW_SIZE: u64 = 4 * 1024 * 1024;
+// 树形预览中默认展开的层数
+const OPEN_DEPTH: usize = 2;
+
+// 预览页面使用的样式
+const STYLE: &str = "\
+body{margin:2em;font:14px/1.5 -apple-system,BlinkMacSystemFont,\"Segoe UI\",Helvetica,Arial,sans-serif;color:#1f2328}\
+h1{font-size:1.4em;margin:0 0 .2em}p.meta{margin:0 0 1em;color:#59636e}\
+table{border-collapse:collapse}th,td{padding:4px 10px;border:1px solid #d1d9e0;text-align:left;white-space:nowrap}\
+th{position:sticky;top:0;background:#f6f8fa;cursor:pointer;user-select:none}\
+th[data-order=asc]::after{content:\" \\25B2\"}th[data-order=desc]::after{content:\" \\25BC\"}\
+tbody tr:nth-child(even){background:#f6f8fa}\
+ul.tree,ul.tree ul{list-style:none;margin:0;padding-left:1.2em}ul.tree{padding:0;font-family:ui-monospace,SFMono-Regular,Menlo,Consolas,monospace}\
+summary{cursor:pointer}.key{color:#0550ae}.size{color:#59636e}\
+.string{color:#0a3069}.number{color:#953800}.bool,.null{color:#cf222e}";
+
+// 点击表头时按该列排序，两列都是数字时按数值比较，再次点击切换升降序
+const SORT_SCRIPT: &str = "\
+document.querySelectorAll('th').forEach(th=>th.addEventListener('click',()=>{\
+const i=th.cellIndex,body=th.closest('table').tBodies[0],asc=th.dataset.order!=='asc';\
+th.parentNode.querySelectorAll('th').forEach(h=>delete h.dataset.order);th.dataset.order=asc?'asc':'desc';\
+const text=r=>r.cells[i]?r.cells[i].textContent:'';\
+const rows=[...body.rows].sort((a,b)=>{const x=text(a),y=text(b),nx=Number(x),ny=Number(y);\
+const c=x!==''&&y!==''&&!isNaN(nx)&&!isNaN(ny)?nx-ny:x.localeCompare(y,undefined,{numeric:true});\
+return asc?c:-c});body.append(...rows)}));";
+
+// 支持预览的文件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum PreviewKind {
+    Csv,
+    Json,
+    Yaml,
+}
+
+impl PreviewKind {
+    // 根据扩展名判断文件是否支持预览
+    pub(super) fn from_path(p: &Path) -> Option<Self> {
+        let ext = p.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "csv" => Some(PreviewKind::Csv),
+            "json" => Some(PreviewKind::Json),
+            "yaml" | "yml" => Some(PreviewKind::Yaml),
+            _ => None,
+        }
+    }
+}
+
+// 判断客户端是否为浏览器（Accept中包含text/html），curl等默认发送*/*的客户端仍然得到原始文件
+pub(super) fn wants_html(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| {
+            accept
+                .split(',')
+                .any(|t| t.split(';').next().unwrap_or("").trim() == "text/html")
+        })
+}
+
+// 返回CSV表格或JSON/YAML树形预览页面，文件过大或无法解析时返回原始内容
+// 响应内容取决于Accept头，因此附带Vary: Accept
+pub(super) async fn serve_preview(
+    state: &HttpServeState,
+    p: &Path,
+    kind: PreviewKind,
+    headers: &HeaderMap,
+) -> Response {
+    let too_large = tokio::fs::metadata(p)
+        .await
+        .is_ok_and(|m| m.len() > MAX_PREVIEW_SIZE);
+    let mut res = if too_large {
+        file::serve_file(state, p, headers).await
+    } else {
+        file::serve_rendered(state, p, headers, "preview", move |content, name| {
+            render(kind, content, name)
+        })
+        .await
+    };
+    vary_accept(&mut res);
+    res
+}
+
+// 在响应的Vary头中追加Accept
+pub(super) fn vary_accept(res: &mut Response) {
+    res.headers_mut()
+        .append(header::VARY, HeaderValue::from_static("accept"));
+}
+
+// 按文件类型生成预览页面
+fn render(kind: PreviewKind, content: &str, name: &str) -> Result<String> {
+    let (meta, mut body) = match kind {
+        PreviewKind::Csv => render_table(content)?,
+        PreviewKind::Json => render_tree(&serde_json::from_str(content)?),
+        PreviewKind::Yaml => render_tree(&serde_yaml::from_str(content)?),
+    };
+    // 只有表格需要排序脚本
+    if kind == PreviewKind::Csv {
+        let _ = writeln!(body, "<script>{}</script>", SORT_SCRIPT);
+    }
+    let name = html_escape(name);
+    Ok(format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{name}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+         <h1>{name}</h1>\n<p class=\"meta\">{meta} &middot; <a href=\"?raw=1\">Raw</a></p>\n\
+         {body}</body>\n</html>\n"
+    ))
+}
+
+// 将CSV内容渲染为可排序的表格，返回行列统计与表格HTML
+fn render_table(content: &str) -> Result<(String, String)> {
+    let (headers, records) = read_csv(content.as_bytes())?;
+    let mut html = String::with_capacity(content.len() * 2);
+    html.push_str("<table>\n<thead><tr>");
+    for field in &headers {
+        let _ = write!(html, "<th>{}</th>", html_escape(field));
+    }
+    html.push_str("</tr></thead>\n<tbody>\n");
+    for record in &records {
+        html.push_str("<tr>");
+        for field in record {
+            let _ = write!(html, "<td>{}</td>", html_escape(field));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</tbody>\n</table>\n");
+    let meta = format!("{} rows &times; {} columns", records.len(), headers.len());
+    Ok((meta, html))
+}
+
+// 将JSON值渲染为可折叠的树，返回顶层类型描述与树HTML
+fn render_tree(value: &Value) -> (String, String) {
+    let mut html = String::from("<ul class=\"tree\">\n");
+    write_node(&mut html, None, value, 0);
+    html.push_str("</ul>\n");
+    (describe(value), html)
+}
+
+// 输出树中的一个节点：对象与数组为可折叠的<details>，其余为单行的值
+fn write_node(html: &mut String, key: Option<&str>, value: &Value, depth: usize) {
+    let key = key
+        .map(|k| format!("<span class=\"key\">{}</span>: ", html_escape(k)))
+        .unwrap_or_default();
+    let children: Vec<(String, &Value)> = match value {
+        Value::Object(map) => map.iter().map(|(k, v)| (k.clone(), v)).collect(),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i.to_string(), v))
+            .collect(),
+        _ => {
+            let _ = writeln!(html, "<li>{}{}</li>", key, scalar(value));
+            return;
+        }
+    };
+    let _ = writeln!(
+        html,
+        "<li><details{}><summary>{}<span class=\"size\">{}</span></summary><ul>",
+        if depth < OPEN_DEPTH { " open" } else { "" },
+        key,
+        describe(value)
+    );
+    for (k, v) in &children {
+        write_node(html, Some(k), v, depth + 1);
+    }
+    html.push_str("</ul></details></li>\n");
+}
+
+// 按类型着色的标量值
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(_) => format!(
+            "<span class=\"string\">{}</span>",
+            html_escape(&value.to_string())
+        ),
+        Value::Number(n) => format!("<span class=\"number\">{}</span>", n),
+        Value::Bool(b) => format!("<span class=\"bool\">{}</span>", b),
+        _ => "<span class=\"null\">null</span>".to_string(),
+    }
+}
+
+// 描述对象与数组的大小，例如{3 keys}、[10 items]
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Object(map) => format!("{{{} keys}}", map.len()),
+        Value::Array(items) => format!("[{} items]", items.len()),
+        _ => "value".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::test_util::test_router;
+    use axum::{
+        body::{to_bytes, Body},
+        http::Request,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_render_preview() -> Result<()> {
+        let page = render(
+            PreviewKind::Csv,
+            "Name,Kit Number\n<b>Buffon</b>,1\nChiellini,3\n",
+            "players.csv",
+        )?;
+        assert!(page.contains("<th>Kit Number</th>"));
+        assert!(page.contains("<td>&lt;b&gt;Buffon&lt;/b&gt;</td><td>1</td>"));
+        assert!(page.contains("2 rows &times; 2 columns"));
+        assert!(page.contains(SORT_SCRIPT));
+        // 列数不一致的CSV无法预览
+        assert!(render(PreviewKind::Csv, "a,b\n1\n", "bad.csv").is_err());
+
+        let page = render(
+            PreviewKind::Json,
+            r#"{"name": "rcli", "tags": ["cli", 1, true, null], "nested": {"deep": {"x": 1}}}"#,
+            "a.json",
+        )?;
+        assert!(page.contains(
+            "<span class=\"key\">name</span>: <span class=\"string\">&quot;rcli&quot;</span>"
+        ));
+        assert!(page.contains("<span class=\"size\">[4 items]</span>"));
+        // 第三层默认折叠
+        assert!(page.contains("<details><summary><span class=\"key\">deep</span>"));
+
+        let page = render(PreviewKind::Yaml, "name: rcli\nport: 8080\n", "a.yaml")?;
+        assert!(page.contains("<span class=\"number\">8080</span>"));
+        assert!(render(PreviewKind::Json, "{", "bad.json").is_err());
+
+        assert_eq!(
+            PreviewKind::from_path(Path::new("assets/juventus.CSV")),
+            Some(PreviewKind::Csv)
+        );
+        assert_eq!(PreviewKind::from_path(Path::new("README.md")), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_wants_html() {
+        let mut headers = HeaderMap::new();
+        assert!(!wants_html(&headers));
+        headers.insert(header::ACCEPT, HeaderValue::from_static("*/*"));
+        assert!(!wants_html(&headers));
+        headers.insert(
+            header::ACCEPT,
+            HeaderValue::from_static("text/html,application/xhtml+xml;q=0.9,*/*;q=0.8"),
+        );
+        assert!(wants_html(&headers));
+    }
+
+    #[tokio::test]
+    async fn test_preview() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let csv = "Name,Kit Number\nBuffon,1\n";
+        std::fs::write(tmp.path().join("players.csv"), csv)?;
+        std::fs::write(tmp.path().join("bad.json"), "{")?;
+        let root = tmp.path().to_str().unwrap();
+        let read = |router: Router, uri: &'static str, accept: &'static str| async move {
+            let req = Request::get(uri)
+                .header(header::ACCEPT, accept)
+                .body(Body::empty())?;
+            let res = router.oneshot(req).await?;
+            let content_type = res.headers()[header::CONTENT_TYPE].clone();
+            let vary = res.headers().get(header::VARY).cloned();
+            let body = to_bytes(res.into_body(), usize::MAX).await?;
+            Ok::<_, anyhow::Error>((content_type, vary, body))
+        };
+        let html = "text/html,application/xhtml+xml,*/*;q=0.8";
+
+        let router = test_router(&["--dir", root]);
+        let (content_type, vary, body) = read(router.clone(), "/players.csv", html).await?;
+        assert_eq!(content_type, "text/html; charset=utf-8");
+        assert_eq!(vary.unwrap(), "accept");
+        assert!(String::from_utf8(body.to_vec())?.contains("<th>Kit Number</th>"));
+
+        // curl等客户端与raw=1得到原始文件
+        let (content_type, vary, body) = read(router.clone(), "/players.csv", "*/*").await?;
+        assert_ne!(content_type, "text/html; charset=utf-8");
+        assert_eq!(vary.unwrap(), "accept");
+        assert_eq!(body, csv.as_bytes());
+        let (_, _, body) = read(router.clone(), "/players.csv?raw=1", html).await?;
+        assert_eq!(body, csv.as_bytes());
+
+        // 无法解析的文件按原样返回
+        let (_, _, body) = read(router, "/bad.json", html).await?;
+        assert_eq!(body, "{".as_bytes());
+
+        let router = test_router(&["--dir", root, "--no-preview"]);
+        let (_, vary, body) = read(router, "/players.csv", html).await?;
+        assert!(vary.is_none());
+        assert_eq!(body, csv.as_bytes());
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Request},
+    http::{
+        header::{self, HeaderMap, HeaderName, HeaderValue},
+        uri::{PathAndQuery, Uri},
+        StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+use hyper_util::{
+    client::legacy::{connect::HttpConnector, Client},
+    rt::TokioExecutor,
+};
+use std::net::SocketAddr;
+use tracing::warn;
+
+use crate::ProxyRoute;
+
+// 逐跳头部只对单个连接有效，转发时需要移除
+const HOP_BY_HOP: [HeaderName; 6] = [
+    header::CONNECTION,
+    header::PROXY_AUTHENTICATE,
+    header::PROXY_AUTHORIZATION,
+    header::TE,
+    header::TRAILER,
+    header::TRANSFER_ENCODING,
+];
+
+// 将请求转发到后端服务的反向代理，请求体与响应体均以流的方式传输
+#[derive(Debug, Clone)]
+pub(super) struct Proxy {
+    route: ProxyRoute,
+    client: Client<HttpConnector, Body>,
+}
+
+impl Proxy {
+    pub(super) fn new(route: ProxyRoute) -> Self {
+        let client = Client::builder(TokioExecutor::new()).build_http();
+        Self { route, client }
+    }
+
+    // 代理的路径前缀
+    pub(super) fn prefix(&self) -> &str {
+        &self.route.prefix
+    }
+
+    // 转发请求，后端不可达时返回502
+    pub(super) async fn forward(self, req: Request) -> Response {
+        let (mut parts, body) = req.into_parts();
+        let uri = match self.target_uri(&parts.uri) {
+            Ok(uri) => uri,
+            Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+        };
+        let remote = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        let host = parts.headers.get(header::HOST).cloned();
+        remove_hop_by_hop(&mut parts.headers);
+        // 与常见开发代理一样把Host改为后端地址，原始Host放入X-Forwarded-Host
+        parts.headers.remove(header::HOST);
+        if let Some(host) = host {
+            parts.headers.insert("x-forwarded-host", host);
+        }
+        if let Some(ip) = remote {
+            append_forwarded_for(&mut parts.headers, &ip.to_string());
+        }
+        parts.uri = uri;
+        // 客户端与代理之间可能是HTTP/2，与后端之间统一使用HTTP/1.1
+        parts.version = axum::http::Version::HTTP_11;
+        let req = Request::from_parts(parts, body);
+        match self.client.request(req).await {
+            Ok(res) => {
+                let (mut parts, body) = res.into_parts();
+                remove_hop_by_hop(&mut parts.headers);
+                Response::from_parts(parts, Body::new(body))
+            }
+            Err(e) => {
+                warn!("Error proxying to {}: {:?}", self.route.target, e);
+                (StatusCode::BAD_GATEWAY, "Bad gateway").into_response()
+            }
+        }
+    }
+
+    // 去掉请求路径中的前缀，拼接到后端地址的路径之后
+    fn target_uri(&self, uri: &Uri) -> Result<Uri, axum::http::Error> {
+        let rest = uri
+            .path()
+            .strip_prefix(&self.route.prefix)
+            .unwrap_or_default();
+        let base = self.route.target.path().trim_end_matches('/');
+        let mut path = format!("{}{}", base, rest);
+        if !path.starts_with('/') {
+            path.insert(0, '/');
+        }
+        if let Some(query) = uri.query() {
+            path = format!("{}?{}", path, query);
+        }
+        let mut target = self.route.target.clone().into_parts();
+        target.path_and_query = Some(PathAndQuery::try_from(path)?);
+        Ok(Uri::from_parts(target)?)
+    }
+}
+
+// 移除逐跳头部，以及Connection头中列出的头部
+fn remove_hop_by_hop(headers: &mut HeaderMap) {
+    let listed: Vec<HeaderName> = headers
+        .get_all(header::CONNECTION)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|name| name.trim().parse().ok())
+        .collect();
+    for name in listed.iter().chain(HOP_BY_HOP.iter()) {
+        headers.remove(name);
+    }
+    headers.remove("keep-alive");
+    headers.remove("proxy-connection");
+}
+
+// 在X-Forwarded-For末尾追加客户端地址
+fn append_forwarded_for(headers: &mut HeaderMap, ip: &str) {
+    let value = match headers.get("x-forwarded-for").and_then(|v| v.to_str().ok()) {
+        Some(prev) => format!("{}, {}", prev, ip),
+        None => ip.to_string(),
+    };
+    if let Ok(value) = HeaderValue::from_str(&value) {
+        headers.insert("x-forwarded-for", value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::test_util::{get_status, test_router};
+    use anyhow::Result;
+    use axum::{body::to_bytes, http::Request, Router};
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_target_uri() {
+        let proxy = Proxy::new("/api=http://127.0.0.1:3000/v1/".parse().unwrap());
+        let uri = |s: &str| proxy.target_uri(&s.parse().unwrap()).unwrap().to_string();
+        assert_eq!(uri("/api"), "http://127.0.0.1:3000/v1");
+        assert_eq!(
+            uri("/api/users?id=1"),
+            "http://127.0.0.1:3000/v1/users?id=1"
+        );
+        let proxy = Proxy::new("/api=http://127.0.0.1:3000".parse().unwrap());
+        assert_eq!(
+            proxy.target_uri(&"/api".parse().unwrap()).unwrap(),
+            "http://127.0.0.1:3000/"
+        );
+    }
+
+    #[test]
+    fn test_remove_hop_by_hop() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONNECTION, HeaderValue::from_static("x-custom"));
+        headers.insert("x-custom", HeaderValue::from_static("1"));
+        headers.insert(
+            header::TRANSFER_ENCODING,
+            HeaderValue::from_static("chunked"),
+        );
+        headers.insert(header::ACCEPT, HeaderValue::from_static("*/*"));
+        remove_hop_by_hop(&mut headers);
+        assert_eq!(headers.len(), 1);
+        assert!(headers.contains_key(header::ACCEPT));
+    }
+
+    #[tokio::test]
+    async fn test_proxy() -> Result<()> {
+        // 本地的axum后端，返回收到的方法、路径、Host与请求体
+        let backend = Router::new().fallback(|req: Request<Body>| async move {
+            let (parts, body) = req.into_parts();
+            let body = to_bytes(body, usize::MAX).await.unwrap();
+            format!(
+                "{} {} {} {}",
+                parts.method,
+                parts.uri,
+                parts.headers[header::HOST].to_str().unwrap(),
+                String::from_utf8_lossy(&body)
+            )
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move { axum::serve(listener, backend).await });
+
+        let route = format!("/api=http://{}/v1", addr);
+        let router = test_router(&["--dir", "fixtures", "--proxy", &route]);
+        let req = Request::post("/api/users?id=1")
+            .header(header::HOST, "localhost:8080")
+            .body(Body::from("hello"))?;
+        let res = router.clone().oneshot(req).await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = to_bytes(res.into_body(), usize::MAX).await?;
+        assert_eq!(
+            String::from_utf8_lossy(&body),
+            format!("POST /v1/users?id=1 {} hello", addr)
+        );
+        // 前缀之外的路径仍然返回静态文件
+        assert_eq!(get_status(&router, "/b64.txt").await, StatusCode::OK);
+        assert_eq!(get_status(&router, "/apifoo").await, StatusCode::NOT_FOUND);
+        Ok(())
+    }
+}
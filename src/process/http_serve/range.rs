@@ -0,0 +1,183 @@
+use axum::http::{header, HeaderMap};
+use std::time::SystemTime;
+
+// Range请求头的解析结果
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum ByteRange {
+    // 返回完整内容（无Range头、格式无法识别或If-Range不匹配）
+    Full,
+    // 返回部分内容，闭区间[start, end]
+    Partial(u64, u64),
+    // 请求的范围无法满足，返回416
+    Unsatisfiable,
+}
+
+// 根据Range与If-Range请求头计算本次需要返回的字节范围
+pub(super) fn requested_range(
+    headers: &HeaderMap,
+    len: u64,
+    modified: Option<SystemTime>,
+    etag: Option<&str>,
+) -> ByteRange {
+    let Some(range) = headers.get(header::RANGE).and_then(|v| v.to_str().ok()) else {
+        return ByteRange::Full;
+    };
+    if let Some(if_range) = headers.get(header::IF_RANGE) {
+        let Ok(if_range) = if_range.to_str() else {
+            return ByteRange::Full;
+        };
+        if !if_range_matches(if_range.trim(), modified, etag) {
+            return ByteRange::Full;
+        }
+    }
+    parse_range(range, len)
+}
+
+// If-Range可以是实体标签或HTTP日期，只有强校验器完全匹配时才返回部分内容
+fn if_range_matches(if_range: &str, modified: Option<SystemTime>, etag: Option<&str>) -> bool {
+    if if_range.starts_with('"') {
+        return etag == Some(if_range);
+    }
+    if if_range.starts_with("W/") {
+        return false;
+    }
+    match (httpdate::parse_http_date(if_range), modified) {
+        (Ok(date), Some(modified)) => date == truncate_to_secs(modified),
+        _ => false,
+    }
+}
+
+// HTTP日期只精确到秒，比较前需要截断
+pub(super) fn truncate_to_secs(t: SystemTime) -> SystemTime {
+    httpdate::parse_http_date(&httpdate::fmt_http_date(t)).unwrap_or(t)
+}
+
+// 解析"bytes=start-end"格式的Range头，只支持单个范围
+// 多个范围或格式错误时按规范忽略该请求头，返回完整内容
+fn parse_range(range: &str, len: u64) -> ByteRange {
+    let Some(spec) = range.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return ByteRange::Full;
+    };
+    match (start.trim(), end.trim()) {
+        // 后缀范围：最后n个字节
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(0) => ByteRange::Unsatisfiable,
+            Ok(_) if len == 0 => ByteRange::Unsatisfiable,
+            Ok(n) => ByteRange::Partial(len.saturating_sub(n), len - 1),
+            Err(_) => ByteRange::Full,
+        },
+        (start, end) => {
+            let Ok(start) = start.parse::<u64>() else {
+                return ByteRange::Full;
+            };
+            let end = if end.is_empty() {
+                u64::MAX
+            } else {
+                match end.parse::<u64>() {
+                    Ok(end) if end >= start => end,
+                    _ => return ByteRange::Full,
+                }
+            };
+            if start >= len {
+                ByteRange::Unsatisfiable
+            } else {
+                ByteRange::Partial(start, end.min(len - 1))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::test_util::test_router;
+    use anyhow::Result;
+    use axum::{
+        body::{to_bytes, Body},
+        http::{HeaderValue, Request, StatusCode},
+    };
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-99", 1000), ByteRange::Partial(0, 99));
+        assert_eq!(
+            parse_range("bytes=900-", 1000),
+            ByteRange::Partial(900, 999)
+        );
+        assert_eq!(
+            parse_range("bytes=-100", 1000),
+            ByteRange::Partial(900, 999)
+        );
+        assert_eq!(parse_range("bytes=-2000", 1000), ByteRange::Partial(0, 999));
+        assert_eq!(
+            parse_range("bytes=500-5000", 1000),
+            ByteRange::Partial(500, 999)
+        );
+        assert_eq!(parse_range("bytes=1000-", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=-0", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-", 0), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=5-1", 1000), ByteRange::Full);
+        assert_eq!(parse_range("bytes=0-1,5-9", 1000), ByteRange::Full);
+        assert_eq!(parse_range("items=0-1", 1000), ByteRange::Full);
+        assert_eq!(parse_range("bytes=abc", 1000), ByteRange::Full);
+    }
+
+    #[test]
+    fn test_if_range() {
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let date = httpdate::fmt_http_date(modified);
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RANGE, HeaderValue::from_static("bytes=0-9"));
+        headers.insert(header::IF_RANGE, HeaderValue::from_str(&date).unwrap());
+        assert_eq!(
+            requested_range(&headers, 100, Some(modified), None),
+            ByteRange::Partial(0, 9)
+        );
+        let changed = modified + Duration::from_secs(10);
+        assert_eq!(
+            requested_range(&headers, 100, Some(changed), None),
+            ByteRange::Full
+        );
+        headers.insert(header::IF_RANGE, HeaderValue::from_static("\"abc\""));
+        assert_eq!(
+            requested_range(&headers, 100, Some(modified), Some("\"abc\"")),
+            ByteRange::Partial(0, 9)
+        );
+        assert_eq!(
+            requested_range(&headers, 100, Some(modified), Some("\"xyz\"")),
+            ByteRange::Full
+        );
+    }
+
+    #[tokio::test]
+    async fn test_range_request() -> Result<()> {
+        let router = test_router(&["--dir", "fixtures"]);
+        let content = std::fs::read("fixtures/b64.txt")?;
+        let req = Request::get("/b64.txt")
+            .header(header::RANGE, "bytes=10-19")
+            .body(Body::empty())?;
+        let res = router.clone().oneshot(req).await?;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            res.headers()[header::CONTENT_RANGE],
+            format!("bytes 10-19/{}", content.len())
+        );
+        let body = to_bytes(res.into_body(), usize::MAX).await?;
+        assert_eq!(&body[..], &content[10..20]);
+
+        let req = Request::get("/b64.txt")
+            .header(header::RANGE, "bytes=100000-")
+            .body(Body::empty())?;
+        let res = router.oneshot(req).await?;
+        assert_eq!(res.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        Ok(())
+    }
+}
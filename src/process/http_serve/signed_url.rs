@@ -0,0 +1,211 @@
+use anyhow::{anyhow, Result};
+use axum::{
+    extract::{Query, Request, State},
+    http::{StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use percent_encoding::percent_decode_str;
+use serde::Deserialize;
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::warn;
+
+use super::{listing::encode_url_path, ServerState};
+use crate::process::text::{Blake3, KeyLoader, TextSign};
+
+// 签名链接携带的查询参数
+#[derive(Debug, Deserialize)]
+struct SignedParams {
+    // 过期时间（Unix时间戳，秒）
+    expires: u64,
+    // 对路径和过期时间的签名
+    sig: String,
+}
+
+// 使用blake3密钥对下载链接进行签名和校验
+pub(super) struct UrlSigner {
+    signer: Blake3,
+}
+
+impl fmt::Debug for UrlSigner {
+    // 不输出密钥内容
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UrlSigner").finish_non_exhaustive()
+    }
+}
+
+impl UrlSigner {
+    // 从文件加载blake3密钥（与rcli text generate-key生成的格式相同）
+    pub(super) fn load(key: &str) -> Result<Self> {
+        Ok(Self {
+            signer: Blake3::load(key)?,
+        })
+    }
+
+    // 计算路径与过期时间的签名
+    fn signature(&self, path: &str, expires: u64) -> Result<blake3::Hash> {
+        let message = format!("{}\n{}", path, expires);
+        let sig = self.signer.sign(&mut message.as_bytes())?;
+        let sig: [u8; 32] = sig
+            .try_into()
+            .map_err(|_| anyhow!("Invalid blake3 signature length"))?;
+        Ok(blake3::Hash::from_bytes(sig))
+    }
+
+    // 生成带有expires和sig参数的相对链接
+    fn sign(&self, path: &str, expires: u64) -> Result<String> {
+        let sig = self.signature(path, expires)?;
+        Ok(format!(
+            "{}?expires={}&sig={}",
+            encode_url_path(path),
+            expires,
+            URL_SAFE_NO_PAD.encode(sig.as_bytes())
+        ))
+    }
+
+    // 校验链接的签名是否有效且未过期
+    fn verify(&self, path: &str, params: &SignedParams, now: u64) -> bool {
+        if params.expires < now {
+            return false;
+        }
+        let Ok(sig) = URL_SAFE_NO_PAD.decode(&params.sig) else {
+            return false;
+        };
+        let Ok(sig) = <[u8; 32]>::try_from(sig) else {
+            return false;
+        };
+        // blake3::Hash的比较是常数时间的
+        self.signature(path, params.expires)
+            .is_ok_and(|expected| expected == blake3::Hash::from_bytes(sig))
+    }
+}
+
+// 生成带过期时间和签名的下载链接
+pub fn process_http_sign_url(
+    path: &str,
+    key: &str,
+    expires: Duration,
+    base_url: &str,
+) -> Result<String> {
+    let signer = UrlSigner::load(key)?;
+    let path = format!("/{}", path.trim_start_matches('/'));
+    let expires = (SystemTime::now() + expires)
+        .duration_since(UNIX_EPOCH)?
+        .as_secs();
+    let url = signer.sign(&path, expires)?;
+    Ok(format!("{}{}", base_url.trim_end_matches('/'), url))
+}
+
+// 签名校验中间件，缺少签名、签名无效或已过期的请求返回403
+pub(super) async fn require_signature(
+    State(state): State<Arc<ServerState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if is_signed(&state, req.uri()) {
+        next.run(req).await
+    } else {
+        forbidden(req.uri())
+    }
+}
+
+// 请求地址是否带有有效的签名，未配置签名密钥时总是通过
+pub(super) fn is_signed(state: &ServerState, uri: &Uri) -> bool {
+    let Some(signer) = &state.url_signer else {
+        return true;
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let path = percent_decode_str(uri.path()).decode_utf8_lossy();
+    Query::<SignedParams>::try_from_uri(uri)
+        .is_ok_and(|Query(params)| signer.verify(&path, &params, now))
+}
+
+// 签名无效或已过期时返回的403响应
+pub(super) fn forbidden(uri: &Uri) -> Response {
+    warn!("Invalid or expired signature for {}", uri);
+    (StatusCode::FORBIDDEN, "Invalid or expired signature").into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::test_util::{get_status, test_router};
+
+    #[test]
+    fn test_sign_verify() -> Result<()> {
+        let signer = UrlSigner::load("fixtures/blake3.txt")?;
+        let url = signer.sign("/docs/a b.txt", 1000)?;
+        assert!(url.starts_with("/docs/a%20b.txt?expires=1000&sig="));
+        let (_, sig) = url.split_once("&sig=").unwrap();
+        let params = SignedParams {
+            expires: 1000,
+            sig: sig.to_string(),
+        };
+        assert!(signer.verify("/docs/a b.txt", &params, 999));
+        assert!(!signer.verify("/docs/a b.txt", &params, 1001));
+        assert!(!signer.verify("/docs/other.txt", &params, 999));
+        let tampered = SignedParams {
+            expires: 2000,
+            sig: sig.to_string(),
+        };
+        assert!(!signer.verify("/docs/a b.txt", &tampered, 999));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_signed_urls() -> Result<()> {
+        let router = test_router(&[
+            "--dir",
+            "fixtures",
+            "--signed-urls-key",
+            "fixtures/blake3.txt",
+        ]);
+        let url = process_http_sign_url(
+            "b64.txt",
+            "fixtures/blake3.txt",
+            std::time::Duration::from_secs(60),
+            "",
+        )?;
+        assert_eq!(get_status(&router, &url).await, StatusCode::OK);
+        assert_eq!(get_status(&router, "/b64.txt").await, StatusCode::FORBIDDEN);
+        let other = url.replace("/b64.txt", "/index.html");
+        assert_eq!(get_status(&router, &other).await, StatusCode::FORBIDDEN);
+
+        // 管理端点不需要签名，模拟路由与回显和文件一样需要签名
+        let tmp = tempfile::tempdir()?;
+        let routes = tmp.path().join("routes.yaml");
+        std::fs::write(&routes, "- path: /api\n  body: mocked\n")?;
+        let router = test_router(&[
+            "--dir",
+            "fixtures",
+            "--signed-urls-key",
+            "fixtures/blake3.txt",
+            "--admin",
+            "health",
+            "--mock",
+            routes.to_str().unwrap(),
+        ]);
+        assert_eq!(get_status(&router, "/_health").await, StatusCode::OK);
+        assert_eq!(get_status(&router, "/b64.txt").await, StatusCode::FORBIDDEN);
+        for path in ["api", "_echo"] {
+            let uri = format!("/{}", path);
+            assert_eq!(get_status(&router, &uri).await, StatusCode::FORBIDDEN);
+            let url = process_http_sign_url(
+                path,
+                "fixtures/blake3.txt",
+                std::time::Duration::from_secs(60),
+                "",
+            )?;
+            assert_eq!(get_status(&router, &url).await, StatusCode::OK, "{}", url);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tracing::warn;
+
+use super::{file, guard, listing::encode_url_path, HttpServeState};
+
+// 单页应用模式下未指定--index时使用的入口文件
+const DEFAULT_INDEX: &str = "index.html";
+
+// 解析请求路径，开启clean_urls时为找不到的无扩展名路径补全.html
+pub(super) fn resolve(state: &HttpServeState, path: &str) -> Result<PathBuf, StatusCode> {
+    match guard::resolve_path(&state.path, path, state.follow_symlinks) {
+        Err(StatusCode::NOT_FOUND) if state.clean_urls && is_clean_url(path) => {
+            guard::resolve_path(
+                &state.path,
+                &format!("{}.html", path),
+                state.follow_symlinks,
+            )
+        }
+        result => result,
+    }
+}
+
+// 目录中存在索引文件时返回该文件
+// 请求路径不以/结尾时先重定向，保证页面中的相对链接指向正确的目录
+pub(super) async fn serve_index(
+    state: &HttpServeState,
+    path: &str,
+    headers: &HeaderMap,
+) -> Option<Response> {
+    let index = state.index.as_deref()?;
+    let p = guard::resolve_path(&state.path, &join(path, index), state.follow_symlinks).ok()?;
+    if !p.is_file() {
+        return None;
+    }
+    if !path.is_empty() && !path.ends_with('/') {
+        let location = encode_url_path(&format!("/{}/", state.url_path(path)));
+        return Some(Redirect::permanent(&location).into_response());
+    }
+    Some(file::serve_file(state, &p, headers).await)
+}
+
+// 处理找不到的路径：单页应用回退到入口文件，其次返回自定义404页面
+pub(super) async fn not_found(state: &HttpServeState, path: &str, headers: &HeaderMap) -> Response {
+    // 带扩展名的路径通常是静态资源，缺失时仍然返回404
+    if state.spa && !has_extension(path) {
+        let index = state.index.as_deref().unwrap_or(DEFAULT_INDEX);
+        if let Ok(p) = guard::resolve_path(&state.path, index, state.follow_symlinks) {
+            if p.is_file() {
+                return file::serve_file(state, &p, headers).await;
+            }
+        }
+    }
+    if let Some(page) = &state.not_found_page {
+        match fs::read(page).await {
+            Ok(body) => {
+                let mime = file::guess_mime(page).unwrap_or_else(|| "text/html".to_string());
+                return (StatusCode::NOT_FOUND, [(header::CONTENT_TYPE, mime)], body)
+                    .into_response();
+            }
+            Err(e) => warn!("Error reading 404 page {:?}: {:?}", page, e),
+        }
+    }
+    (StatusCode::NOT_FOUND, format!("File {:?} not found", path)).into_response()
+}
+
+// 拼接目录路径与文件名
+fn join(dir: &str, name: &str) -> String {
+    if dir.is_empty() || dir.ends_with('/') {
+        format!("{}{}", dir, name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+// 路径的最后一段是否带有扩展名
+fn has_extension(path: &str) -> bool {
+    Path::new(path).extension().is_some()
+}
+
+// 可以补全.html的路径：非空、不以/结尾且没有扩展名
+fn is_clean_url(path: &str) -> bool {
+    !path.is_empty() && !path.ends_with('/') && !has_extension(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::test_util::{get_status, test_router};
+    use anyhow::Result;
+    use axum::{
+        body::{to_bytes, Body},
+        http::{header, Request, StatusCode},
+    };
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_clean_url() {
+        assert!(is_clean_url("about"));
+        assert!(is_clean_url("docs/intro"));
+        assert!(!is_clean_url(""));
+        assert!(!is_clean_url("docs/"));
+        assert!(!is_clean_url("about.html"));
+        assert_eq!(join("", "index.html"), "index.html");
+        assert_eq!(join("docs", "index.html"), "docs/index.html");
+        assert_eq!(join("docs/", "index.html"), "docs/index.html");
+    }
+
+    #[tokio::test]
+    async fn test_static_site() -> Result<()> {
+        let index = std::fs::read("fixtures/index.html")?;
+        let router = test_router(&["--dir", "fixtures", "--index", "index.html", "--spa"]);
+        for uri in ["/", "/app/settings"] {
+            let res = router
+                .clone()
+                .oneshot(Request::get(uri).body(Body::empty())?)
+                .await?;
+            assert_eq!(res.status(), StatusCode::OK, "{}", uri);
+            let body = to_bytes(res.into_body(), usize::MAX).await?;
+            assert_eq!(&body[..], &index[..], "{}", uri);
+        }
+        assert_eq!(
+            get_status(&router, "/missing.js").await,
+            StatusCode::NOT_FOUND
+        );
+
+        let router = test_router(&[
+            "--dir",
+            "fixtures",
+            "--clean-urls",
+            "--404-page",
+            "fixtures/index.html",
+        ]);
+        assert_eq!(get_status(&router, "/index").await, StatusCode::OK);
+        let res = router
+            .oneshot(Request::get("/missing").body(Body::empty())?)
+            .await?;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert!(res.headers()[header::CONTENT_TYPE]
+            .to_str()?
+            .starts_with("text/html"));
+        let body = to_bytes(res.into_body(), usize::MAX).await?;
+        assert_eq!(&body[..], &index[..]);
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+// 各模块的路由级测试共用的辅助函数
+use super::{build_router, ServerState};
+use crate::HttpServeOpts;
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    Router,
+};
+use clap::Parser;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+// 按命令行参数构建完整的路由，参数与http serve相同
+pub(super) fn test_router(args: &[&str]) -> Router {
+    let opts = HttpServeOpts::parse_from(std::iter::once("serve").chain(args.iter().copied()));
+    build_router(Arc::new(ServerState::try_new(&opts).unwrap())).unwrap()
+}
+
+// 发送GET请求，返回状态码
+pub(super) async fn get_status(router: &Router, uri: &str) -> StatusCode {
+    let req = Request::get(uri).body(Body::empty()).unwrap();
+    router.clone().oneshot(req).await.unwrap().status()
+}
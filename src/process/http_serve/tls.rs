@@ -0,0 +1,57 @@
+use anyhow::Result;
+use axum_server::tls_rustls::RustlsConfig;
+use std::net::IpAddr;
+use tracing::{info, warn};
+
+use crate::HttpServeOpts;
+
+// 根据命令行选项加载TLS配置，未启用HTTPS时返回None
+pub(super) async fn load_tls_config(opts: &HttpServeOpts) -> Result<Option<RustlsConfig>> {
+    // axum-server只启用了rustls的接口，需要显式安装ring加密后端
+    // 重复安装会返回错误，可以安全忽略
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    if let (Some(cert), Some(key)) = (&opts.tls_cert, &opts.tls_key) {
+        info!("Loading TLS certificate {:?}", cert);
+        let config = RustlsConfig::from_pem_file(cert, key).await?;
+        return Ok(Some(config));
+    }
+    if opts.tls_self_signed {
+        let config = self_signed_config(opts.bind).await?;
+        return Ok(Some(config));
+    }
+    Ok(None)
+}
+
+// 生成仅在本次运行中有效的自签名证书
+async fn self_signed_config(bind: IpAddr) -> Result<RustlsConfig> {
+    let mut names = vec![
+        "localhost".to_string(),
+        "127.0.0.1".to_string(),
+        "::1".to_string(),
+    ];
+    if !bind.is_unspecified() && !bind.is_loopback() {
+        names.push(bind.to_string());
+    }
+    let certified = rcgen::generate_simple_self_signed(names.clone())?;
+    warn!(
+        "Using a throwaway self-signed certificate for {:?}, clients must skip verification (e.g. curl -k)",
+        names
+    );
+    let cert = certified.cert.der().to_vec();
+    let key = certified.signing_key.serialize_der();
+    Ok(RustlsConfig::from_der(vec![cert], key).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_self_signed_config() -> Result<()> {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let config = self_signed_config("127.0.0.1".parse()?).await?;
+        let alpn = &config.get_inner().alpn_protocols;
+        assert!(alpn.contains(&b"h2".to_vec()));
+        Ok(())
+    }
+}
@@ -0,0 +1,341 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{Multipart, Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+};
+use futures::{Stream, StreamExt};
+use std::{
+    fmt::Display,
+    io::ErrorKind,
+    path::{Path as FsPath, PathBuf},
+    sync::Arc,
+};
+use tokio::{fs, io::AsyncWriteExt};
+use tracing::{info, warn};
+
+use super::{
+    guard,
+    listing::{dir_url_path, encode_url_path},
+    HttpServeState,
+};
+
+// 上传失败时返回的状态码与错误信息
+type UploadError = (StatusCode, String);
+
+// 处理PUT /path请求，将请求体写入目标文件
+pub(super) async fn put_handler(
+    State(state): State<Arc<HttpServeState>>,
+    Path(path): Path<String>,
+    body: Body,
+) -> Response {
+    let dest = match guard::resolve_new_path(&state.path, &path, state.follow_symlinks) {
+        Ok(dest) => dest,
+        Err(status) => return (status, format!("Cannot upload to {:?}", path)).into_response(),
+    };
+    match save_stream(&state, &dest, body.into_data_stream()).await {
+        Ok(status) => status.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// 处理根目录的multipart表单上传
+pub(super) async fn post_root_handler(
+    State(state): State<Arc<HttpServeState>>,
+    headers: HeaderMap,
+    multipart: Multipart,
+) -> Response {
+    save_multipart(&state, "", &headers, multipart).await
+}
+
+// 处理子目录的multipart表单上传
+pub(super) async fn post_handler(
+    State(state): State<Arc<HttpServeState>>,
+    Path(path): Path<String>,
+    headers: HeaderMap,
+    multipart: Multipart,
+) -> Response {
+    save_multipart(&state, &path, &headers, multipart).await
+}
+
+// 将表单中所有带文件名的字段保存到目标目录
+async fn save_multipart(
+    state: &HttpServeState,
+    dir: &str,
+    headers: &HeaderMap,
+    mut multipart: Multipart,
+) -> Response {
+    let dir = dir.trim_matches('/');
+    let mut saved = Vec::new();
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return (e.status(), e.body_text()).into_response(),
+        };
+        // 只取文件名的最后一段，忽略客户端提供的目录部分
+        let Some(name) = field
+            .file_name()
+            .and_then(|name| FsPath::new(name).file_name())
+            .and_then(|name| name.to_str())
+            .map(str::to_string)
+        else {
+            continue;
+        };
+        let req_path = if dir.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", dir, name)
+        };
+        let dest = match guard::resolve_new_path(&state.path, &req_path, state.follow_symlinks) {
+            Ok(dest) => dest,
+            Err(status) => {
+                return (status, format!("Cannot upload to {:?}", req_path)).into_response()
+            }
+        };
+        if let Err(e) = save_stream(state, &dest, field).await {
+            return e.into_response();
+        }
+        saved.push(name);
+    }
+    // 浏览器表单提交后跳转回目录列表页面
+    let from_browser = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("text/html"));
+    if from_browser {
+        let location = encode_url_path(&dir_url_path(&state.url_path(dir)));
+        Redirect::to(&location).into_response()
+    } else {
+        (StatusCode::CREATED, saved.join("\n")).into_response()
+    }
+}
+
+// 将数据流先写入同目录下的临时文件，完成后再原子地重命名为目标文件
+// 新建文件返回201，覆盖已有文件返回204
+async fn save_stream<S, E>(
+    state: &HttpServeState,
+    dest: &FsPath,
+    mut stream: S,
+) -> Result<StatusCode, UploadError>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: Display,
+{
+    let existed = fs::symlink_metadata(dest).await.is_ok();
+    if existed && fs::metadata(dest).await.is_ok_and(|m| m.is_dir()) {
+        return Err((StatusCode::CONFLICT, "Target is a directory".to_string()));
+    }
+    if existed && state.no_clobber {
+        return Err((StatusCode::CONFLICT, "File already exists".to_string()));
+    }
+    let mut tmp = TempFile {
+        path: temp_path(dest),
+        committed: false,
+    };
+    let result = write_temp(&tmp.path, &mut stream, state.max_upload_size).await;
+    let result = match result {
+        Ok(size) => commit(&tmp.path, dest, state.no_clobber)
+            .await
+            .map(|_| size),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(size) => {
+            tmp.committed = true;
+            info!("Uploaded {} bytes to {:?}", size, dest);
+            Ok(if existed {
+                StatusCode::NO_CONTENT
+            } else {
+                StatusCode::CREATED
+            })
+        }
+        Err(e) => {
+            warn!("Upload to {:?} failed: {}", dest, e.1);
+            Err(e)
+        }
+    }
+}
+
+// 写入临时文件，超过大小限制时返回413
+async fn write_temp<S, E>(tmp: &FsPath, stream: &mut S, max_size: u64) -> Result<u64, UploadError>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: Display,
+{
+    let mut file = fs::File::create(tmp).await.map_err(internal_error)?;
+    let mut size = 0u64;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+        size += chunk.len() as u64;
+        if size > max_size {
+            return Err((
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("Upload exceeds the limit of {} bytes", max_size),
+            ));
+        }
+        file.write_all(&chunk).await.map_err(internal_error)?;
+    }
+    file.sync_all().await.map_err(internal_error)?;
+    Ok(size)
+}
+
+// 将临时文件移动到目标位置
+// no_clobber时使用硬链接，目标已存在会原子地失败，避免并发上传相互覆盖
+async fn commit(tmp: &FsPath, dest: &FsPath, no_clobber: bool) -> Result<(), UploadError> {
+    if no_clobber {
+        match fs::hard_link(tmp, dest).await {
+            Ok(()) => {
+                let _ = fs::remove_file(tmp).await;
+                Ok(())
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                Err((StatusCode::CONFLICT, "File already exists".to_string()))
+            }
+            Err(e) => Err(internal_error(e)),
+        }
+    } else {
+        fs::rename(tmp, dest).await.map_err(internal_error)
+    }
+}
+
+// 上传的临时文件，未移动到目标位置就被释放时删除
+// 上传失败或请求被取消（例如客户端断开连接）时都不会留下临时文件
+struct TempFile {
+    path: PathBuf,
+    // 是否已移动到目标位置
+    committed: bool,
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+// 临时文件与目标文件位于同一目录，保证重命名是原子操作
+fn temp_path(dest: &FsPath) -> PathBuf {
+    let name = dest
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    dest.with_file_name(format!(
+        ".{}.{:016x}.rcli-upload",
+        name,
+        rand::random::<u64>()
+    ))
+}
+
+// 是否为上传写入的临时文件，见temp_path
+pub(super) fn is_temp_file(p: &FsPath) -> bool {
+    p.extension().is_some_and(|ext| ext == "rcli-upload")
+}
+
+fn internal_error(e: std::io::Error) -> UploadError {
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::test_util::test_router;
+    use anyhow::Result;
+    use axum::{body::Body, http::Request};
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_temp_path() {
+        let tmp = temp_path(FsPath::new("docs/a.txt"));
+        assert!(is_temp_file(&tmp));
+        assert!(tmp
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with(".a.txt."));
+        assert!(!is_temp_file(FsPath::new("docs/a.txt")));
+    }
+
+    #[tokio::test]
+    async fn test_upload() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let root = tmp.path().to_str().unwrap();
+        let router = test_router(&["--dir", root, "--upload", "--max-upload-size", "16"]);
+        let put = |uri: &str, body: &'static str| {
+            let req = Request::put(uri).body(Body::from(body)).unwrap();
+            router.clone().oneshot(req)
+        };
+        assert_eq!(put("/a.txt", "hello").await?.status(), StatusCode::CREATED);
+        assert_eq!(std::fs::read_to_string(tmp.path().join("a.txt"))?, "hello");
+        assert_eq!(
+            put("/a.txt", "world").await?.status(),
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(std::fs::read_to_string(tmp.path().join("a.txt"))?, "world");
+        assert_eq!(
+            put("/big.txt", "0123456789abcdefg").await?.status(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+        assert!(!tmp.path().join("big.txt").exists());
+        assert_eq!(
+            put("/%2e%2e/a.txt", "x").await?.status(),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            put("/missing/a.txt", "x").await?.status(),
+            StatusCode::CONFLICT
+        );
+
+        let body = "--XX\r\nContent-Disposition: form-data; name=\"file\"; filename=\"../b.txt\"\r\n\r\nform\r\n--XX--\r\n";
+        let req = Request::post("/")
+            .header(header::CONTENT_TYPE, "multipart/form-data; boundary=XX")
+            .body(Body::from(body))?;
+        let res = router.clone().oneshot(req).await?;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(std::fs::read_to_string(tmp.path().join("b.txt"))?, "form");
+        // 上传完成后不应残留临时文件
+        assert_eq!(std::fs::read_dir(tmp.path())?.count(), 2);
+
+        let router = test_router(&["--dir", root, "--upload", "--no-clobber"]);
+        let req = Request::put("/a.txt").body(Body::from("again"))?;
+        assert_eq!(router.oneshot(req).await?.status(), StatusCode::CONFLICT);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_upload_timeout() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let root = tmp.path().to_str().unwrap();
+        let router = test_router(&["--dir", root, "--upload", "--request-timeout", "50ms"]);
+        // 上传不受--request-timeout限制，较慢的请求体也能完整写入
+        let slow = futures::stream::once(async { Ok::<_, std::io::Error>("hello") }).chain(
+            futures::stream::once(async {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                Ok(" world")
+            }),
+        );
+        let req = Request::put("/slow.txt").body(Body::from_stream(slow))?;
+        assert_eq!(
+            router.clone().oneshot(req).await?.status(),
+            StatusCode::CREATED
+        );
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("slow.txt"))?,
+            "hello world"
+        );
+
+        // 请求被取消时删除已写入的临时文件
+        let stalled = futures::stream::once(async { Ok::<_, std::io::Error>("partial") })
+            .chain(futures::stream::pending());
+        let req = Request::put("/stalled.txt").body(Body::from_stream(stalled))?;
+        let res = tokio::time::timeout(Duration::from_millis(100), router.oneshot(req)).await;
+        assert!(res.is_err());
+        let names: Vec<_> = std::fs::read_dir(tmp.path())?
+            .map(|e| e.map(|e| e.file_name()))
+            .collect::<std::io::Result<_>>()?;
+        assert_eq!(names, ["slow.txt"]);
+        Ok(())
+    }
+}
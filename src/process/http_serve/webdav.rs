@@ -0,0 +1,289 @@
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use dav_server::{localfs::LocalFs, memls::MemLs, DavHandler};
+use futures::{future, StreamExt};
+use percent_encoding::percent_decode_str;
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+use tracing::warn;
+
+use super::{guard, limits, HttpServeState};
+
+// 基于本地目录的WebDAV处理器，锁只保存在内存中，重启后失效
+#[derive(Clone)]
+pub(super) struct WebDav {
+    handler: DavHandler,
+}
+
+impl fmt::Debug for WebDav {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebDav").finish_non_exhaustive()
+    }
+}
+
+impl WebDav {
+    // base为挂载点的路径前缀，根目录为空字符串
+    pub(super) fn new(root: &Path, base: &str, follow_symlinks: bool) -> Self {
+        let mut config = DavHandler::builder()
+            .filesystem(LocalFs::new(root, false, false, false))
+            .locksystem(MemLs::new())
+            // 目录列表与文件下载仍由静态文件服务处理，这里只隐藏PROPFIND结果中的符号链接
+            .hide_symlinks(!follow_symlinks);
+        if !base.is_empty() {
+            config = config.strip_prefix(base);
+        }
+        Self {
+            handler: config.build_handler(),
+        }
+    }
+}
+
+// GET/HEAD/POST之外的请求（PROPFIND、MKCOL、MOVE、COPY、DELETE、PUT、LOCK等）交给WebDAV处理
+pub(super) async fn handle(
+    State(state): State<Arc<HttpServeState>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let Some(webdav) = &state.webdav else {
+        return next.run(req).await;
+    };
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::POST) {
+        return next.run(req).await;
+    }
+    // LocalFs本身不限制符号链接，这里对请求路径与MOVE/COPY的目标路径做同样的目录检查
+    if let Err(status) = check_path(&state, req.uri().path()) {
+        return status.into_response();
+    }
+    if let Some(dest) = req.headers().get("destination") {
+        let dest = dest
+            .to_str()
+            .ok()
+            .and_then(|d| d.parse::<Uri>().ok())
+            .ok_or(StatusCode::BAD_REQUEST)
+            .and_then(|d| check_path(&state, d.path()));
+        if let Err(status) = dest {
+            return status.into_response();
+        }
+    }
+    // 写入操作中途被中断会留下不完整的文件或目录树，不受--request-timeout限制
+    if is_write(req.method()) {
+        limits::exempt_from_timeout(&req);
+    }
+    if *req.method() == Method::PUT {
+        return put(&state, webdav, req).await;
+    }
+    // --no-clobber时COPY/MOVE不覆盖已有的目标，目标存在时返回412
+    if state.no_clobber && matches!(req.method().as_str(), "COPY" | "MOVE") {
+        req.headers_mut()
+            .insert("overwrite", HeaderValue::from_static("F"));
+    }
+    webdav.handler.handle(req).await.map(Body::new)
+}
+
+// 写入的文件大小与上传共用--max-upload-size限制，超出时删除写了一半的新文件并返回413
+async fn put(state: &HttpServeState, webdav: &WebDav, req: Request) -> Response {
+    let max_size = state.max_upload_size;
+    let too_large = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+        .is_some_and(|len| len > max_size);
+    if too_large {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    }
+    let dest = local_path(state, req.uri().path()).ok().flatten();
+    let existed = dest.as_deref().is_some_and(Path::exists);
+    // 与上传相同，--no-clobber时不覆盖已有的文件
+    if existed && state.no_clobber {
+        return (StatusCode::CONFLICT, "File already exists").into_response();
+    }
+    let exceeded = Arc::new(AtomicBool::new(false));
+    let flag = exceeded.clone();
+    let req = req.map(|body| {
+        let stream = body.into_data_stream().scan(0u64, move |written, chunk| {
+            let chunk = chunk.map_err(io::Error::other).and_then(|chunk| {
+                *written += chunk.len() as u64;
+                if *written > max_size {
+                    flag.store(true, Ordering::Relaxed);
+                    Err(io::Error::other("upload too large"))
+                } else {
+                    Ok(chunk)
+                }
+            });
+            future::ready(Some(chunk))
+        });
+        Body::from_stream(stream)
+    });
+    let res = webdav.handler.handle(req).await.map(Body::new);
+    if !exceeded.load(Ordering::Relaxed) {
+        return res;
+    }
+    if let (Some(dest), false) = (dest, existed) {
+        if let Err(e) = tokio::fs::remove_file(&dest).await {
+            warn!("Failed to remove partial upload {:?}: {}", dest, e);
+        }
+    }
+    StatusCode::PAYLOAD_TOO_LARGE.into_response()
+}
+
+// 修改文件或目录的WebDAV方法
+fn is_write(method: &Method) -> bool {
+    matches!(
+        method.as_str(),
+        "PUT" | "DELETE" | "MKCOL" | "COPY" | "MOVE" | "PROPPATCH"
+    )
+}
+
+// 检查请求路径是否位于挂载目录之内，尚不存在的路径检查其所在目录
+fn check_path(state: &HttpServeState, uri_path: &str) -> Result<(), StatusCode> {
+    local_path(state, uri_path).map(|_| ())
+}
+
+// 将请求路径转换为挂载目录下的本地路径，不属于当前挂载点或父目录不存在时返回None
+fn local_path(state: &HttpServeState, uri_path: &str) -> Result<Option<PathBuf>, StatusCode> {
+    let path = percent_decode_str(uri_path).decode_utf8_lossy();
+    let Some(relative) = path.strip_prefix(state.base.as_str()) else {
+        // 目标不在当前挂载点内，由WebDAV处理器返回相应的错误
+        return Ok(None);
+    };
+    let relative = relative.trim_matches('/');
+    match guard::resolve_path(&state.path, relative, state.follow_symlinks) {
+        Ok(p) => Ok(Some(p)),
+        Err(StatusCode::NOT_FOUND) => {
+            match guard::resolve_new_path(&state.path, relative, state.follow_symlinks) {
+                Ok(p) => Ok(Some(p)),
+                // 父目录不存在时由WebDAV处理器返回409
+                Err(StatusCode::CONFLICT) => Ok(None),
+                Err(status) => Err(status),
+            }
+        }
+        Err(status) => {
+            warn!("Rejected WebDAV request for {:?}", path);
+            Err(status)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::http_serve::test_util::{get_status, test_router};
+    use anyhow::Result;
+    use axum::{
+        body::{to_bytes, Body},
+        http::Request,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_webdav() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let share = tmp.path().join("share");
+        std::fs::create_dir(&share)?;
+        let root = tmp.path().to_str().unwrap();
+        let mount = format!("/share={}", share.display());
+        let args = ["--dir", root, "--mount", &mount, "--max-upload-size", "16"];
+        assert_eq!(
+            send(&test_router(&args), "PROPFIND", "/share/", None).await?,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+
+        let mut args = args.to_vec();
+        args.push("--webdav");
+        let router = test_router(&args);
+        assert_eq!(
+            send(&router, "MKCOL", "/share/docs", None).await?,
+            StatusCode::CREATED
+        );
+        let req = Request::put("/share/docs/a.txt").body(Body::from("hello"))?;
+        assert_eq!(
+            router.clone().oneshot(req).await?.status(),
+            StatusCode::CREATED
+        );
+        assert_eq!(std::fs::read_to_string(share.join("docs/a.txt"))?, "hello");
+        assert_eq!(
+            send(&router, "COPY", "/share/docs/a.txt", Some("/share/b.txt")).await?,
+            StatusCode::CREATED
+        );
+        assert_eq!(
+            send(&router, "MOVE", "/share/docs/a.txt", Some("/share/c.txt")).await?,
+            StatusCode::CREATED
+        );
+        assert_eq!(std::fs::read_to_string(share.join("c.txt"))?, "hello");
+        assert_eq!(
+            send(&router, "DELETE", "/share/docs", None).await?,
+            StatusCode::NO_CONTENT
+        );
+        assert!(!share.join("docs").exists());
+
+        let req = Request::builder()
+            .method("PROPFIND")
+            .uri("/share/")
+            .header("depth", "1")
+            .body(Body::empty())?;
+        let res = router.clone().oneshot(req).await?;
+        assert_eq!(res.status(), StatusCode::MULTI_STATUS);
+        let body = String::from_utf8(to_bytes(res.into_body(), usize::MAX).await?.to_vec())?;
+        assert!(body.contains("<D:href>/share/b.txt</D:href>"));
+
+        // GET仍由静态文件服务处理
+        assert_eq!(get_status(&router, "/share/c.txt").await, StatusCode::OK);
+        assert_eq!(
+            send(&router, "MOVE", "/share/c.txt", Some("/share/%2e%2e/x.txt")).await?,
+            StatusCode::FORBIDDEN
+        );
+        let req = Request::put("/share/big.txt").body(Body::from("0123456789abcdefg"))?;
+        assert_eq!(
+            router.clone().oneshot(req).await?.status(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+        assert!(!share.join("big.txt").exists());
+
+        // --no-clobber时PUT、COPY与MOVE都不覆盖已有文件
+        args.push("--no-clobber");
+        let router = test_router(&args);
+        let req = Request::put("/share/c.txt").body(Body::from("again"))?;
+        assert_eq!(
+            router.clone().oneshot(req).await?.status(),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(std::fs::read_to_string(share.join("c.txt"))?, "hello");
+        for method in ["COPY", "MOVE"] {
+            assert_eq!(
+                send(&router, method, "/share/b.txt", Some("/share/c.txt")).await?,
+                StatusCode::PRECONDITION_FAILED
+            );
+        }
+        let req = Request::put("/share/d.txt").body(Body::from("new"))?;
+        assert_eq!(router.oneshot(req).await?.status(), StatusCode::CREATED);
+        Ok(())
+    }
+
+    // 发送不带请求体的WebDAV请求，返回状态码
+    async fn send(
+        router: &Router,
+        method: &str,
+        uri: &str,
+        destination: Option<&str>,
+    ) -> Result<StatusCode> {
+        let mut req = Request::builder().method(method).uri(uri);
+        if let Some(dest) = destination {
+            req = req.header("destination", dest);
+        }
+        let res = router.clone().oneshot(req.body(Body::empty())?).await?;
+        Ok(res.status())
+    }
+}
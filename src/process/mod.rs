@@ -1,11 +1,13 @@
 mod b64;
 mod csv_convert;
 mod gen_pass;
+mod http_request;
 mod http_serve;
 mod text;
 
 pub use b64::{process_decode, process_encode};
 pub use csv_convert::process_csv;
 pub use gen_pass::process_genpass;
-pub use http_serve::process_http_serve;
+pub use http_request::{process_http_file, process_http_request, HttpFileReport};
+pub use http_serve::{process_http_fetch, process_http_serve, process_http_sign_url, FetchSummary};
 pub use text::{process_generate_key, process_text_sign, process_text_verify};
@@ -18,7 +18,6 @@ impl TextSign for Blake3 {
         // todo: improve performance by reading in chunks
         let mut buf = Vec::<u8>::new();
         reader.read_to_end(&mut buf)?;
-        println!("buf1: {:?}", String::from_utf8_lossy(&buf));
         Ok(blake3::keyed_hash(&self.key, &buf).as_bytes().to_vec())
     }
 }
@@ -49,10 +48,8 @@ impl TextVerify for Blake3 {
     fn verify(&self, mut reader: impl Read, signature: &[u8]) -> Result<bool> {
         let mut buf = Vec::<u8>::new();
         reader.read_to_end(&mut buf)?;
-        println!("buf: {:?}", String::from_utf8_lossy(&buf));
         let hash = blake3::keyed_hash(&self.key, &buf);
         let hash = hash.as_bytes();
-        println!("new_sig: {}", URL_SAFE_NO_PAD.encode(hash));
         Ok(hash == signature)
     }
 }
@@ -93,6 +90,7 @@ impl KeyLoader for Blake3 {
     }
 }
 
+#[derive(Debug)]
 pub struct Ed25519Signer {
     key: SigningKey,
 }
@@ -123,6 +121,7 @@ impl KeyLoader for Ed25519Signer {
         Self::try_new(&key)
     }
 }
+#[derive(Debug)]
 pub struct Ed25519Verifier {
     key: VerifyingKey,
 }